@@ -0,0 +1,257 @@
+//! Structured representation of a parsed Doxygen comment.
+
+/// A parsed Doxygen comment, as a sequence of blocks in source order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocComment {
+    pub blocks: Vec<Block>,
+    /// Whether the item is internal, i.e. marked `@private` or only documented within `@internal`
+    /// sections, so that it could be `#[doc(hidden)]`.
+    pub internal: bool,
+}
+
+impl DocComment {
+    /// Returns the brief description, i.e. the leading paragraph.
+    pub fn brief(&self) -> Option<&[Inline]> {
+        match self.blocks.first() {
+            Some(Block::Paragraph(inlines)) => Some(inlines),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the top-level paragraphs following the brief description.
+    pub fn details(&self) -> impl Iterator<Item = &[Inline]> {
+        let skip = usize::from(self.brief().is_some());
+        self.blocks
+            .iter()
+            .skip(skip)
+            .filter_map(|block| match block {
+                Block::Paragraph(inlines) => Some(inlines.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// Iterates over all documented parameters.
+    pub fn params(&self) -> impl Iterator<Item = &Param> {
+        self.blocks.iter().flat_map(|block| match block {
+            Block::Params(params) => params.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Returns the description of the return value.
+    pub fn returns(&self) -> Option<&[Block]> {
+        self.blocks.iter().find_map(|block| match block {
+            Block::Returns(content) => Some(content.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Iterates over all documented template parameters.
+    pub fn type_params(&self) -> impl Iterator<Item = &Param> {
+        self.blocks.iter().flat_map(|block| match block {
+            Block::TypeParams(params) => params.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Iterates over all documented return values.
+    pub fn return_values(&self) -> impl Iterator<Item = &ReturnValue> {
+        self.blocks.iter().flat_map(|block| match block {
+            Block::ReturnValues(values) => values.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Iterates over all documented exceptions.
+    pub fn exceptions(&self) -> impl Iterator<Item = &Exception> {
+        self.blocks.iter().flat_map(|block| match block {
+            Block::Exceptions(exceptions) => exceptions.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Iterates over all see-also references, skipping entries of free text.
+    pub fn see_also(&self) -> impl Iterator<Item = &Reference> {
+        let entries = self.blocks.iter().flat_map(|block| match block {
+            Block::SeeAlso(entries) => entries.as_slice(),
+            _ => &[],
+        });
+        entries.filter_map(|entry| entry.reference.as_ref())
+    }
+}
+
+/// A block-level element of a comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Block {
+    /// Running text.
+    Paragraph(Vec<Inline>),
+    /// A paragraph with a title, from `@par`.
+    TitledParagraph {
+        title: Vec<Inline>,
+        content: Vec<Block>,
+    },
+    /// A section heading, from `@section` (level 1), `@subsection`, `@subsubsection` or
+    /// `@paragraph` (level 4).
+    Section {
+        level: u8,
+        /// The name `@ref` refers to the section by, or empty if it has none.
+        anchor: String,
+        title: Vec<Inline>,
+    },
+    /// A list, from `@li`, `@arg` or markdown `-`, `*` and `+` items, or a numbered list from
+    /// `-#` and `1.` items. Nested lists are part of the item they are indented below.
+    List {
+        /// Whether the items are numbered.
+        ordered: bool,
+        items: Vec<Vec<Block>>,
+    },
+    /// A code block, from `@code` or `@verbatim`.
+    Code {
+        /// Language of the code, e.g. `c` for `@code{.c}`, or `text` for `@verbatim`.
+        language: Option<String>,
+        code: String,
+    },
+    /// Raw HTML, from `@htmlonly[block]`.
+    Html(String),
+    /// A callout, from `@note`, `@since`, `@deprecated`, `@remarks`, `@warning`, `@attention` or
+    /// `@important`.
+    Note { kind: NoteKind, content: Vec<Block> },
+    /// Consecutive `@param` entries.
+    Params(Vec<Param>),
+    /// Consecutive `@tparam` entries.
+    TypeParams(Vec<Param>),
+    /// The `@return` description.
+    Returns(Vec<Block>),
+    /// Consecutive `@retval` entries.
+    ReturnValues(Vec<ReturnValue>),
+    /// Consecutive `@throws` entries.
+    Exceptions(Vec<Exception>),
+    /// Consecutive `@pre`, `@post` or `@invariant` conditions of the same kind, one item each.
+    Conditions {
+        kind: ConditionKind,
+        items: Vec<Vec<Block>>,
+    },
+    /// Consecutive `@see` entries, one per comma-separated reference.
+    SeeAlso(Vec<SeeAlso>),
+}
+
+/// The kind of a [`Block::Note`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoteKind {
+    Note,
+    Since,
+    Deprecated,
+    Remark,
+    Warning,
+    Attention,
+    Important,
+}
+
+impl NoteKind {
+    /// Returns whether the callout warns the reader, like `@warning`.
+    pub fn is_warning(self) -> bool {
+        matches!(
+            self,
+            NoteKind::Warning | NoteKind::Attention | NoteKind::Important
+        )
+    }
+}
+
+/// The kind of a [`Block::Conditions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConditionKind {
+    /// A precondition, from `@pre`.
+    Pre,
+    /// A postcondition, from `@post`.
+    Post,
+    /// An invariant, from `@invariant`.
+    Invariant,
+}
+
+/// A documented function or template parameter.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    /// Attributes from the `[...]` list, e.g. `in` and `optional`.
+    pub attributes: Vec<String>,
+    pub description: Vec<Block>,
+}
+
+impl Param {
+    /// Returns the data flow direction given in the attribute list.
+    pub fn direction(&self) -> Option<Direction> {
+        let has = |name: &str| self.attributes.iter().any(|attr| attr == name);
+        match (has("in"), has("out")) {
+            _ if has("inout") => Some(Direction::InOut),
+            (true, true) => Some(Direction::InOut),
+            (true, false) => Some(Direction::In),
+            (false, true) => Some(Direction::Out),
+            (false, false) => None,
+        }
+    }
+}
+
+/// The data flow direction of a [`Param`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+    InOut,
+}
+
+/// A documented return value, such as a status code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReturnValue {
+    pub value: String,
+    pub description: Vec<Block>,
+}
+
+/// A documented exception, from `@throws` or `@exception`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Exception {
+    /// The exception type.
+    pub name: String,
+    pub description: Vec<Block>,
+}
+
+/// An entry of a `@see` section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SeeAlso {
+    /// The referenced item or URL, or `None` if the entry is free text.
+    pub reference: Option<Reference>,
+    /// Text following the reference, or the free text of the entry.
+    pub description: Vec<Block>,
+}
+
+/// A reference to another item or a URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Reference {
+    pub target: String,
+    /// Text of the link instead of the target, from `@link`, `{@link}` or a quoted `@ref`
+    /// argument.
+    pub text: Option<String>,
+}
+
+/// An inline span of text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Inline {
+    Text(String),
+    /// A code span, from `@c`, `@p` or `{@code}`.
+    Code(String),
+    /// An emphasized word, from `@a`, `@e` or `@em`.
+    Emphasis(String),
+    /// A bold word, from `@b`.
+    Strong(String),
+    /// A reference, from `@ref`, `@link` or `{@link}`.
+    Ref(Reference),
+    /// Raw HTML, from `@htmlonly`.
+    Html(String),
+    /// A link target in the text, from `@anchor`.
+    Anchor(String),
+    /// A line break within a paragraph.
+    SoftBreak,
+    /// A line break that is kept when rendered, from `<br>`.
+    LineBreak,
+}
@@ -0,0 +1,277 @@
+//! Ready-made [`ParseCallbacks`] for bindgen.
+
+use crate::error::Severity;
+use crate::render::{Renderer, Rustdoc};
+use crate::transformer::Transformer;
+use bindgen::FieldVisibilityKind;
+use bindgen::callbacks::{
+    AttributeInfo, DeriveInfo, DeriveTrait, DiscoveredItem, DiscoveredItemId,
+    EnumVariantCustomBehavior, EnumVariantValue, FieldInfo, ImplementsTrait, IntKind, ItemInfo,
+    MacroParsingBehavior, ParseCallbacks, Token,
+};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Transforms the comments of generated bindings.
+///
+/// Malformed commands are reported as `cargo:warning` lines, each at most once per build, and
+/// unknown commands only if [`DoxygenCallbacks::warn_unknown_commands`] is set. All other hooks
+/// are forwarded to the callbacks given to [`DoxygenCallbacks::chain`], if any.
+///
+/// ```no_run
+/// let bindings = bindgen::builder()
+///     .header("wrapper.h")
+///     .parse_callbacks(Box::new(doxygen_bindgen::DoxygenCallbacks::new()))
+///     .generate();
+/// ```
+pub struct DoxygenCallbacks<R = Rustdoc> {
+    transformer: Transformer<R>,
+    /// Whether malformed comments are kept as bindgen produced them instead of being
+    /// transformed leniently.
+    strict: bool,
+    /// Whether unknown commands such as `@file` are reported as well.
+    warn_unknown_commands: bool,
+    inner: Option<Box<dyn ParseCallbacks>>,
+    /// Warnings emitted so far.
+    warned: Mutex<HashSet<String>>,
+}
+
+impl DoxygenCallbacks {
+    /// Creates callbacks using the default [`Transformer`].
+    pub fn new() -> Self {
+        Self::with_transformer(Transformer::default())
+    }
+}
+
+impl Default for DoxygenCallbacks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Renderer> DoxygenCallbacks<R> {
+    /// Creates callbacks using the given [`Transformer`].
+    pub fn with_transformer(transformer: Transformer<R>) -> Self {
+        DoxygenCallbacks {
+            transformer,
+            strict: false,
+            warn_unknown_commands: false,
+            inner: None,
+            warned: Mutex::default(),
+        }
+    }
+
+    /// Leaves comments that fail to parse untransformed, instead of keeping the malformed
+    /// commands as text.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Also reports text that is likely not meant as a command, such as an unknown `@file`, which
+    /// is common in headers and therefore not reported by default.
+    pub fn warn_unknown_commands(mut self, warn: bool) -> Self {
+        self.warn_unknown_commands = warn;
+        self
+    }
+
+    /// Forwards all hooks except [`ParseCallbacks::process_comment`] to `callbacks`.
+    pub fn chain(mut self, callbacks: impl ParseCallbacks + 'static) -> Self {
+        self.inner = Some(Box::new(callbacks));
+        self
+    }
+
+    /// Prints a warning, unless the same warning was printed before.
+    fn warn(&self, message: String) {
+        let mut warned = self.warned.lock().unwrap_or_else(|err| err.into_inner());
+        if warned.insert(message.clone()) {
+            for line in message.lines() {
+                println!("cargo:warning={line}");
+            }
+        }
+    }
+}
+
+impl<R> fmt::Debug for DoxygenCallbacks<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DoxygenCallbacks")
+            .field("strict", &self.strict)
+            .field("warn_unknown_commands", &self.warn_unknown_commands)
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Renderer> ParseCallbacks for DoxygenCallbacks<R> {
+    fn process_comment(&self, comment: &str) -> Option<String> {
+        if self.strict {
+            return match self.transformer.transform(comment) {
+                Ok(res) => Some(res),
+                Err(err) => {
+                    self.warn(format!("Problem processing doxygen comment: {err}"));
+                    None
+                }
+            };
+        }
+        let (res, diagnostics) = self.transformer.transform_lenient(comment);
+        let diagnostics = diagnostics.into_iter().filter(|diagnostic| {
+            diagnostic.severity == Severity::Error || self.warn_unknown_commands
+        });
+        for diagnostic in diagnostics {
+            self.warn(format!("Problem processing doxygen comment: {diagnostic}"));
+        }
+        Some(res)
+    }
+
+    fn will_parse_macro(&self, name: &str) -> MacroParsingBehavior {
+        let inner = self.inner.as_ref();
+        inner.map_or_else(MacroParsingBehavior::default, |cb| {
+            cb.will_parse_macro(name)
+        })
+    }
+
+    fn generated_name_override(&self, item_info: ItemInfo<'_>) -> Option<String> {
+        self.inner.as_ref()?.generated_name_override(item_info)
+    }
+
+    fn generated_link_name_override(&self, item_info: ItemInfo<'_>) -> Option<String> {
+        self.inner.as_ref()?.generated_link_name_override(item_info)
+    }
+
+    fn modify_macro(&self, name: &str, tokens: &mut Vec<Token>) {
+        if let Some(inner) = &self.inner {
+            inner.modify_macro(name, tokens);
+        }
+    }
+
+    fn int_macro(&self, name: &str, value: i64) -> Option<IntKind> {
+        self.inner.as_ref()?.int_macro(name, value)
+    }
+
+    fn str_macro(&self, name: &str, value: &[u8]) {
+        if let Some(inner) = &self.inner {
+            inner.str_macro(name, value);
+        }
+    }
+
+    fn func_macro(&self, name: &str, value: &[&[u8]]) {
+        if let Some(inner) = &self.inner {
+            inner.func_macro(name, value);
+        }
+    }
+
+    fn enum_variant_behavior(
+        &self,
+        enum_name: Option<&str>,
+        original_variant_name: &str,
+        variant_value: EnumVariantValue,
+    ) -> Option<EnumVariantCustomBehavior> {
+        self.inner
+            .as_ref()?
+            .enum_variant_behavior(enum_name, original_variant_name, variant_value)
+    }
+
+    fn enum_variant_name(
+        &self,
+        enum_name: Option<&str>,
+        original_variant_name: &str,
+        variant_value: EnumVariantValue,
+    ) -> Option<String> {
+        self.inner
+            .as_ref()?
+            .enum_variant_name(enum_name, original_variant_name, variant_value)
+    }
+
+    fn item_name(&self, item_info: ItemInfo) -> Option<String> {
+        self.inner.as_ref()?.item_name(item_info)
+    }
+
+    fn header_file(&self, filename: &str) {
+        if let Some(inner) = &self.inner {
+            inner.header_file(filename);
+        }
+    }
+
+    fn include_file(&self, filename: &str) {
+        if let Some(inner) = &self.inner {
+            inner.include_file(filename);
+        }
+    }
+
+    fn read_env_var(&self, key: &str) {
+        if let Some(inner) = &self.inner {
+            inner.read_env_var(key);
+        }
+    }
+
+    fn blocklisted_type_implements_trait(
+        &self,
+        name: &str,
+        derive_trait: DeriveTrait,
+    ) -> Option<ImplementsTrait> {
+        self.inner
+            .as_ref()?
+            .blocklisted_type_implements_trait(name, derive_trait)
+    }
+
+    fn add_derives(&self, info: &DeriveInfo<'_>) -> Vec<String> {
+        let inner = self.inner.as_ref();
+        inner.map_or_else(Vec::new, |cb| cb.add_derives(info))
+    }
+
+    fn add_attributes(&self, info: &AttributeInfo<'_>) -> Vec<String> {
+        let inner = self.inner.as_ref();
+        inner.map_or_else(Vec::new, |cb| cb.add_attributes(info))
+    }
+
+    fn field_visibility(&self, info: FieldInfo<'_>) -> Option<FieldVisibilityKind> {
+        self.inner.as_ref()?.field_visibility(info)
+    }
+
+    fn new_item_found(&self, id: DiscoveredItemId, item: DiscoveredItem) {
+        if let Some(inner) = &self.inner {
+            inner.new_item_found(id, item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct U8Macros;
+
+    impl ParseCallbacks for U8Macros {
+        fn int_macro(&self, _name: &str, _value: i64) -> Option<IntKind> {
+            Some(IntKind::U8)
+        }
+
+        fn process_comment(&self, _comment: &str) -> Option<String> {
+            Some(String::new())
+        }
+    }
+
+    #[test]
+    fn process_comment() {
+        let callbacks = DoxygenCallbacks::new().chain(U8Macros);
+        let res = callbacks.process_comment("Opens a file.\n@param\n@param[in] Name The name.");
+        assert_eq!(
+            res.as_deref(),
+            Some("Opens a file.\n@param\n\n# Arguments\n\n* `Name` [in]  - The name.")
+        );
+        callbacks.process_comment("@param\n");
+        assert_eq!(callbacks.warned.lock().unwrap().len(), 2);
+        callbacks.process_comment("@file foo.h");
+        assert_eq!(callbacks.warned.lock().unwrap().len(), 2);
+        let verbose = DoxygenCallbacks::new().warn_unknown_commands(true);
+        verbose.process_comment("@file foo.h");
+        assert_eq!(verbose.warned.lock().unwrap().len(), 1);
+        assert_eq!(callbacks.int_macro("FLAG", 1), Some(IntKind::U8));
+
+        let strict = DoxygenCallbacks::new().strict(true);
+        assert_eq!(strict.process_comment("@param\n"), None);
+        assert_eq!(strict.int_macro("FLAG", 1), None);
+    }
+}
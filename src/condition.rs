@@ -0,0 +1,67 @@
+//! Evaluation of the section label expressions of `@if` and `@cond`.
+
+/// Evaluates a label expression such as `WIN64 && !(ARM || ARM64)` against the enabled labels.
+///
+/// Returns `None` if the expression is malformed.
+pub(crate) fn evaluate(expr: &str, enabled: &[String]) -> Option<bool> {
+    let mut evaluator = Evaluator {
+        rest: expr.trim(),
+        enabled,
+    };
+    let res = evaluator.or()?;
+    evaluator.rest.is_empty().then_some(res)
+}
+
+struct Evaluator<'a> {
+    rest: &'a str,
+    enabled: &'a [String],
+}
+
+impl Evaluator<'_> {
+    /// Consumes `token`, if the expression continues with it.
+    fn token(&mut self, token: &str) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest.trim_start();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn or(&mut self) -> Option<bool> {
+        let mut res = self.and()?;
+        while self.token("||") {
+            res |= self.and()?;
+        }
+        Some(res)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut res = self.unary()?;
+        while self.token("&&") {
+            res &= self.unary()?;
+        }
+        Some(res)
+    }
+
+    fn unary(&mut self) -> Option<bool> {
+        if self.token("!") {
+            return self.unary().map(|res| !res);
+        }
+        if self.token("(") {
+            let res = self.or()?;
+            return self.token(")").then_some(res);
+        }
+        let end = self
+            .rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '-' && c != '.')
+            .unwrap_or(self.rest.len());
+        let (label, rest) = self.rest.split_at(end);
+        if label.is_empty() {
+            return None;
+        }
+        self.rest = rest.trim_start();
+        Some(self.enabled.iter().any(|enabled| enabled == label))
+    }
+}
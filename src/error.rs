@@ -0,0 +1,135 @@
+//! Errors reported while parsing comments.
+
+use std::error::Error;
+use std::fmt;
+
+/// A position within the comment being parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the comment.
+    pub offset: usize,
+    /// One-based line number.
+    pub line: usize,
+    /// One-based column, counted in characters.
+    pub column: usize,
+    /// The full line containing the position.
+    pub source_line: String,
+}
+
+impl Location {
+    /// Resolves a byte offset into `source`.
+    pub(crate) fn new(source: &str, offset: usize) -> Self {
+        let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        Location {
+            offset,
+            line: source[..offset].matches('\n').count() + 1,
+            column: source[start..offset].chars().count() + 1,
+            source_line: source[start..end].trim_end_matches('\r').to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    /// Renders the source line with a caret under the position.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        let padding = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(f, "{gutter} | {padding}^")
+    }
+}
+
+/// An error in the structure of a comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TransformError {
+    /// A `@param` attribute list without its closing bracket.
+    MalformedParamAttributes { location: Location },
+    /// A `@param` without a parameter name.
+    MissingParamName { location: Location },
+    /// A `@retval` without a value.
+    MissingReturnValue { location: Location },
+    /// A `@throws` without an exception type.
+    MissingExceptionName { location: Location },
+    /// An `@if` or `@cond` whose section label expression is malformed.
+    MalformedCondition { location: Location },
+    /// A block command such as `@code` without its closing command.
+    UnterminatedBlock { command: String, location: Location },
+    /// A command that is not supported.
+    UnknownCommand { command: String, location: Location },
+}
+
+impl TransformError {
+    /// Returns where in the comment the error occurred.
+    pub fn location(&self) -> &Location {
+        match self {
+            TransformError::MalformedParamAttributes { location }
+            | TransformError::MissingParamName { location }
+            | TransformError::MissingReturnValue { location }
+            | TransformError::MissingExceptionName { location }
+            | TransformError::MalformedCondition { location }
+            | TransformError::UnterminatedBlock { location, .. }
+            | TransformError::UnknownCommand { location, .. } => location,
+        }
+    }
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransformError::MalformedParamAttributes { .. } => {
+                write!(f, "Expected closing ']' inside attribute list")?
+            }
+            TransformError::MissingParamName { .. } => write!(f, "Expected parameter name")?,
+            TransformError::MissingReturnValue { .. } => write!(f, "Expected return value")?,
+            TransformError::MissingExceptionName { .. } => write!(f, "Expected exception type")?,
+            TransformError::MalformedCondition { .. } => {
+                write!(f, "Expected section label expression")?
+            }
+            TransformError::UnterminatedBlock { command, .. } => {
+                write!(f, "Unterminated @{command} block")?
+            }
+            TransformError::UnknownCommand { command, .. } => {
+                write!(f, "Unknown command @{command}")?
+            }
+        }
+        let location = self.location();
+        write!(f, " at {}:{}\n{location}", location.line, location.column)
+    }
+}
+
+impl Error for TransformError {}
+
+/// How severe a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// Text that is likely not meant as a command, such as an unknown command.
+    Warning,
+    /// A malformed command.
+    Error,
+}
+
+/// A problem found while parsing leniently, whose text was kept verbatim.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: TransformError,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.error),
+            Severity::Error => write!(f, "error: {}", self.error),
+        }
+    }
+}
@@ -1,100 +1,33 @@
-use std::error::Error;
-use yap::{IntoTokens, Tokens};
+mod ast;
+#[cfg(feature = "bindgen")]
+mod callbacks;
+mod condition;
+mod error;
+mod parse;
+mod render;
+mod tags;
+mod transformer;
 
-const SEPS: [char; 5] = [' ', '\t', '\r', '\n', '['];
+pub use ast::{
+    Block, ConditionKind, Direction, DocComment, Exception, Inline, NoteKind, Param, Reference,
+    ReturnValue, SeeAlso,
+};
+#[cfg(feature = "bindgen")]
+pub use callbacks::DoxygenCallbacks;
+pub use error::{Diagnostic, Location, Severity, TransformError};
+pub use parse::{parse, parse_lenient};
+pub use render::{CommonMark, Html, PlainText, Renderer, Rustdoc};
+pub use transformer::{Options, ReturnValueStyle, TitleStyle, Transformer, TransformerBuilder};
 
-/// Formats a reference string as markdown.
-fn format_ref(str: String) -> String {
-    if str.contains("://") {
-        format!("[{str}]({str})")
-    } else {
-        format!("[`{str}`]")
-    }
-}
-
-/// Extracts the next word token.
-fn take_word(toks: &mut impl Tokens<Item = char>) -> String {
-    toks.take_while(|&c| !SEPS.into_iter().any(|s| c == s))
-        .collect::<String>()
-}
-
-/// Skips whitespace tokens.
-fn skip_whitespace(toks: &mut impl Tokens<Item = char>) {
-    toks.skip_while(|c| c.is_ascii_whitespace());
-}
-
-/// Emits a section header if it's not already emitted.
-fn emit_section_header(output: &mut Vec<String>, header: &str) {
-    if !output.iter().any(|line| line.trim() == header) {
-
-        // inspect previous token, transform single new line into two new lines
-        if let Some(last) = output.last_mut() {
-            if last == "\n" {
-                last.push('\n');
-            }
-        }
-
-        output.push(header.to_owned());
-        output.push("\n\n".to_owned());
-    }
+/// Transforms Doxygen comments into markdown for Rustdoc, using the default [`Options`].
+pub fn transform(str: &str) -> Result<String, TransformError> {
+    Transformer::<Rustdoc>::default().transform(str)
 }
 
-/// Transforms Doxygen comments into markdown for Rustdoc.
-pub fn transform(str: &str) -> Result<String, Box<dyn Error>> {
-    let mut res: Vec<String> = vec![];
-    let mut toks = str.into_tokens();
-
-    skip_whitespace(&mut toks);
-    while let Some(tok) = toks.next() {
-        if "@\\".chars().any(|c| c == tok) {
-            let tag = take_word(&mut toks);
-            skip_whitespace(&mut toks);
-            match tag.as_str() {
-                "param" => {
-                    emit_section_header(&mut res, "# Arguments");
-                    let (mut argument, mut attributes) = (take_word(&mut toks), "".to_owned());
-                    if argument.is_empty() {
-                        if toks.next() != Some('[') {
-                            return Err("Expected opening '[' inside attribute list".into());
-                        }
-                        attributes = toks.take_while(|&c| c != ']').collect::<String>();
-                        if toks.next() != Some(']') {
-                            return Err("Expected closing ']' inside attribute list".into());
-                        }
-                        attributes = format!(" [{}] ", attributes);
-                        skip_whitespace(&mut toks);
-                        argument = take_word(&mut toks);
-                    }
-                    res.push(format!("* `{}`{} -", argument, attributes));
-                }
-                "c" | "p" => res.push(format!("`{}`", take_word(&mut toks))),
-                "ref" => res.push(format_ref(take_word(&mut toks))),
-                "see" | "sa" => {
-                    emit_section_header(&mut res, "# See also");
-                    res.push(format!("> {}", format_ref(take_word(&mut toks))));
-                }
-                "a" | "e" | "em" => res.push(format!("_{}_", take_word(&mut toks))),
-                "b" => res.push(format!("**{}**", take_word(&mut toks))),
-                "note" => res.push("> **Note** ".to_owned()),
-                "since" => res.push("> **Since** ".to_owned()),
-                "deprecated" => res.push("> **Deprecated** ".to_owned()),
-                "remark" | "remarks" => res.push("> ".to_owned()),
-                "li" => res.push("- ".to_owned()),
-                "par" => res.push("# ".to_owned()),
-                "returns" | "return" | "result" => emit_section_header(&mut res, "# Returns"),
-                "{" => { /* group start, not implemented  */ }
-                "}" => { /* group end, not implemented */ }
-                "brief" | "short" => {}
-                _ => res.push(format!("{tok}{tag} ")),
-            }
-        } else if tok == '\n' {
-            skip_whitespace(&mut toks);
-            res.push(format!("{tok}"));
-        } else {
-            res.push(format!("{tok}"));
-        }
-    }
-    Ok(res.join(""))
+/// Transforms Doxygen comments into markdown for Rustdoc, keeping malformed commands as text
+/// instead of failing.
+pub fn transform_lenient(str: &str) -> (String, Vec<Diagnostic>) {
+    Transformer::<Rustdoc>::default().transform_lenient(str)
 }
 
 #[cfg(test)]
@@ -109,7 +42,7 @@ mod tests {
     #[test]
     fn with_sections() {
         const S: &str = " The NtDelayExecution routine suspends the current thread until the specified condition is met.\n\n @param Alertable The function returns when either the time-out period has elapsed or when the APC function is called.\n @param DelayInterval The time interval for which execution is to be suspended, in milliseconds.\n - A value of zero causes the thread to relinquish the remainder of its time slice to any other thread that is ready to run.\n - If there are no other threads ready to run, the function returns immediately, and the thread continues execution.\n - A value of INFINITE indicates that the suspension should not time out.\n @return NTSTATUS Successful or errant status. The return value is STATUS_USER_APC when Alertable is TRUE, and the function returned due to one or more I/O completion callback functions.\n @remarks Note that a ready thread is not guaranteed to run immediately. Consequently, the thread will not run until some arbitrary time after the sleep interval elapses,\n based upon the system \"tick\" frequency and the load factor from other processes.\n @see https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-sleepex";
-        const S_: &str = "The NtDelayExecution routine suspends the current thread until the specified condition is met.\n\n# Arguments\n\n* `Alertable` - The function returns when either the time-out period has elapsed or when the APC function is called.\n* `DelayInterval` - The time interval for which execution is to be suspended, in milliseconds.\n- A value of zero causes the thread to relinquish the remainder of its time slice to any other thread that is ready to run.\n- If there are no other threads ready to run, the function returns immediately, and the thread continues execution.\n- A value of INFINITE indicates that the suspension should not time out.\n\n# Returns\n\nNTSTATUS Successful or errant status. The return value is STATUS_USER_APC when Alertable is TRUE, and the function returned due to one or more I/O completion callback functions.\n> Note that a ready thread is not guaranteed to run immediately. Consequently, the thread will not run until some arbitrary time after the sleep interval elapses,\nbased upon the system \"tick\" frequency and the load factor from other processes.\n\n# See also\n\n> [https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-sleepex](https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-sleepex)";
+        const S_: &str = "The NtDelayExecution routine suspends the current thread until the specified condition is met.\n\n# Arguments\n\n* `Alertable` - The function returns when either the time-out period has elapsed or when the APC function is called.\n* `DelayInterval` - The time interval for which execution is to be suspended, in milliseconds.\n  - A value of zero causes the thread to relinquish the remainder of its time slice to any other thread that is ready to run.\n  - If there are no other threads ready to run, the function returns immediately, and the thread continues execution.\n  - A value of INFINITE indicates that the suspension should not time out.\n\n# Returns\n\nNTSTATUS Successful or errant status. The return value is STATUS_USER_APC when Alertable is TRUE, and the function returned due to one or more I/O completion callback functions.\n> Note that a ready thread is not guaranteed to run immediately. Consequently, the thread will not run until some arbitrary time after the sleep interval elapses,\n> based upon the system \"tick\" frequency and the load factor from other processes.\n\n# See also\n\n- [https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-sleepex](https://learn.microsoft.com/en-us/windows/win32/api/synchapi/nf-synchapi-sleepex)";
         assert_eq!(crate::transform(S).unwrap(), S_);
     }
 
@@ -122,8 +55,584 @@ mod tests {
 
     #[test]
     fn new_paragraph_after_html() {
-        const S: &str =  "Set encoding parameters to default values:\n<ul>\n<li>Lossless</li>\n<li>1 tile\n</li>\n<li>etc...</li>\n</ul>\n@param parameters Compression parameters";
+        const S: &str = "Set encoding parameters to default values:\n<ul>\n<li>Lossless</li>\n<li>1 tile\n</li>\n<li>etc...</li>\n</ul>\n@param parameters Compression parameters";
         const S_: &str = "Set encoding parameters to default values:\n<ul>\n<li>Lossless</li>\n<li>1 tile\n</li>\n<li>etc...</li>\n</ul>\n\n# Arguments\n\n* `parameters` - Compression parameters";
         assert_eq!(crate::transform(S).unwrap(), S_);
     }
+
+    #[test]
+    fn parse_tree() {
+        use crate::{Block, Direction, Inline, Reference};
+
+        const S: &str = "Opens a @b file.\n\n@param[in, optional] Name The @c UNICODE_STRING name.\n@return NTSTATUS\n@see NtClose";
+        let doc = crate::parse(S).unwrap();
+        assert_eq!(
+            doc.brief().unwrap(),
+            [
+                Inline::Text("Opens a ".into()),
+                Inline::Strong("file.".into())
+            ]
+        );
+
+        let param = doc.params().next().unwrap();
+        assert_eq!(param.name, "Name");
+        assert_eq!(param.attributes, ["in", "optional"]);
+        assert_eq!(param.direction(), Some(Direction::In));
+        assert_eq!(
+            param.description,
+            [Block::Paragraph(vec![
+                Inline::Text("The ".into()),
+                Inline::Code("UNICODE_STRING".into()),
+                Inline::Text(" name.".into()),
+            ])]
+        );
+        assert_eq!(
+            doc.returns().unwrap(),
+            [Block::Paragraph(vec![Inline::Text("NTSTATUS".into())])]
+        );
+        assert_eq!(
+            doc.see_also().collect::<Vec<_>>(),
+            [&Reference {
+                target: "NtClose".into(),
+                text: None,
+            }]
+        );
+    }
+
+    const SAMPLE: &str = "Closes a @c HANDLE object.\n@param[in] Handle The handle,\nas returned by @ref NtOpenFile or similar.\n@note Not thread-safe.\n@see https://example.com";
+
+    #[test]
+    fn render_commonmark() {
+        const S_: &str = "Closes a `HANDLE` object.\n\n# Arguments\n\n* `Handle` [in]  - The handle,\n  as returned by `NtOpenFile` or similar.\n> **Note** Not thread-safe.\n\n# See also\n\n- [https://example.com](https://example.com)";
+        let transformer = crate::Transformer::builder()
+            .renderer(crate::CommonMark)
+            .build();
+        assert_eq!(transformer.transform(SAMPLE).unwrap(), S_);
+    }
+
+    #[test]
+    fn render_plain_text() {
+        const S_: &str = "Closes a HANDLE object.\n\nArguments:\n  Handle [in] - The handle, as returned by NtOpenFile or similar.\n\nNote: Not thread-safe.\n\nSee also:\n  https://example.com";
+        let transformer = crate::Transformer::builder()
+            .renderer(crate::PlainText)
+            .build();
+        assert_eq!(transformer.transform(SAMPLE).unwrap(), S_);
+    }
+
+    #[test]
+    fn render_html() {
+        const S_: &str = "<p>Closes a <code>HANDLE</code> object.</p>\n<h1>Arguments</h1>\n<ul>\n<li><code>Handle</code> [in] -\n<p>The handle,\nas returned by <code>NtOpenFile</code> or similar.</p>\n</li>\n</ul>\n<blockquote>\n<strong>Note</strong>\n<p>Not thread-safe.</p>\n</blockquote>\n<h1>See also</h1>\n<ul>\n<li><a href=\"https://example.com\">https://example.com</a></li>\n</ul>\n";
+        let transformer = crate::Transformer::builder().renderer(crate::Html).build();
+        assert_eq!(transformer.transform(SAMPLE).unwrap(), S_);
+
+        // only parsed markup is kept
+        let res = transformer.transform("a < b & c<script>x</script>\n@htmlonly<hr>@endhtmlonly");
+        assert_eq!(
+            res.unwrap(),
+            "<p>a &lt; b &amp; c&lt;script&gt;x&lt;/script&gt;\n<hr></p>\n"
+        );
+    }
+
+    #[test]
+    fn with_options() {
+        const S: &str = "Waits for an object.\n@par Remarks\n\\li Alertable waits return early.\n@param Handle The object.\n@note Blocks the thread.\n@return NTSTATUS";
+        const S_: &str = "Waits for an object.\n\n### Remarks\n* Alertable waits return early.\n\n### Parameters\n\n- `Handle` - The object.\n> **Caution** Blocks the thread.\n\n### Result\n\nNTSTATUS";
+        let transformer = crate::Transformer::builder()
+            .heading_level(3)
+            .arguments_title("Parameters")
+            .returns_title("Result")
+            .note_label("Caution")
+            .bullet('*')
+            .param_bullet('-')
+            .build();
+        assert_eq!(transformer.transform(S).unwrap(), S_);
+
+        // levels past the deepest heading are clamped rather than overflowing
+        let options = crate::Options {
+            heading_level: 255,
+            section_level: 255,
+            ..Default::default()
+        };
+        let transformer = crate::Transformer::builder().options(options).build();
+        let res = transformer
+            .transform("@section s Usage\n@return NTSTATUS")
+            .unwrap();
+        assert_eq!(
+            res,
+            "###### <a id=\"s\"></a>Usage\n\n###### Returns\n\nNTSTATUS"
+        );
+    }
+
+    #[test]
+    fn error_location() {
+        fn assert_send_sync<T: Send + Sync + 'static>(_: &T) {}
+
+        const S: &str = "Opens a key.\n\n\t@param[in KeyHandle The key.";
+        const E_: &str = "Expected closing ']' inside attribute list at 3:30\n  |\n3 | \t@param[in KeyHandle The key.\n  | \t                            ^";
+        let err = crate::transform(S).unwrap_err();
+        assert_send_sync(&err);
+        assert!(matches!(
+            err,
+            crate::TransformError::MalformedParamAttributes { .. }
+        ));
+        assert_eq!(err.location().offset, S.len());
+        assert_eq!(err.to_string(), E_);
+
+        let err = crate::transform("@param\n").unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TransformError::MissingParamName { .. }
+        ));
+        assert_eq!((err.location().line, err.location().column), (1, 7));
+    }
+
+    #[test]
+    fn lenient() {
+        use crate::{Severity, TransformError};
+
+        const S: &str = "Queries a value.\n@param\n@param[in Key The key, see @foo or user@example.com.\n@param[out] Value Receives the value.";
+        const S_: &str = "Queries a value.\n@param\n@param[in Key The key, see @foo or user@example.com.\n\n# Arguments\n\n* `Value` [out]  - Receives the value.";
+        let (res, diagnostics) = crate::transform_lenient(S);
+        assert_eq!(res, S_);
+        assert_eq!(diagnostics.len(), 3);
+        assert!(matches!(
+            diagnostics[0].error,
+            TransformError::MissingParamName { .. }
+        ));
+        assert!(matches!(
+            diagnostics[1].error,
+            TransformError::MalformedParamAttributes { .. }
+        ));
+        assert!(
+            matches!(&diagnostics[2].error, TransformError::UnknownCommand { command, .. } if command == "foo")
+        );
+        assert_eq!(diagnostics[2].severity, Severity::Warning);
+        assert!(crate::transform(S).is_err());
+    }
+
+    #[test]
+    fn code_blocks() {
+        const S: &str = "Example:\n @code{.c}\n if (x) {\n     // @param is kept\n     foo(\"```\");\n }\n @endcode\n @verbatim\n   @b raw text\n @endverbatim\n Done.";
+        const S_: &str = "Example:\n\n````c\nif (x) {\n    // @param is kept\n    foo(\"```\");\n}\n````\n\n```text\n@b raw text\n```\n\nDone.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let err = crate::transform("@code\nint x;").unwrap_err();
+        assert!(
+            matches!(&err, crate::TransformError::UnterminatedBlock { command, .. } if command == "code")
+        );
+        assert_eq!(err.location().offset, 0);
+    }
+
+    #[test]
+    fn code_languages() {
+        const S: &str = " Example:\n ```\n int x;\n ```\n ~~~{.cpp}\n auto y = x;\n ~~~\n\n     if (x)\n\n         y();\n\n Done.\n @code{.rust}\n let z;\n @endcode";
+        const S_: &str = "Example:\n\n```text\nint x;\n```\n\n```cpp\nauto y = x;\n```\n\n```text\nif (x)\n\n    y();\n```\n\nDone.\n\n```text\nlet z;\n```";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder().code_language("c").build();
+        let res = transformer
+            .transform("@code{.no_run}\nint x;\n@endcode")
+            .unwrap();
+        assert_eq!(res, "```c\nint x;\n```");
+
+        // indented lines continue a paragraph or list item
+        const L: &str = "Text\n    more text\n\n- item\n\n    continued\n- next";
+        const L_: &str = "Text\nmore text\n- item\n\n  continued\n- next";
+        assert_eq!(crate::transform(L).unwrap(), L_);
+
+        // whitespace-only lines are not code
+        assert_eq!(
+            crate::transform("Opens.\n\n\t\nDetails.").unwrap(),
+            "Opens.\n\nDetails."
+        );
+        assert_eq!(
+            crate::transform("    \nOpens a file.").unwrap(),
+            "Opens a file."
+        );
+
+        // code stays in the list item or condition it follows
+        const N: &str = "- item:\n  ```\n  x\n  ```\n- next\n@pre A:\n@code\ny\n@endcode\n@param z Z:\n@htmlonly[block]<hr>@endhtmlonly\n@param w W.";
+        const N_: &str = "- item:\n\n  ```text\n  x\n  ```\n- next\n\n# Safety\n\n- A:\n\n  ```text\n  y\n  ```\n\n# Arguments\n\n* `z` - Z:\n\n  <hr>\n* `w` - W.";
+        assert_eq!(crate::transform(N).unwrap(), N_);
+    }
+
+    #[test]
+    fn multi_line_notes() {
+        const S: &str = "@deprecated Use @c Foo instead,\n which is faster.\n\nNot quoted.\n@note First line\nsecond line\n@since 1.2\n@return Zero.";
+        const S_: &str = "> **Deprecated** Use `Foo` instead,\n> which is faster.\n\nNot quoted.\n> **Note** First line\n> second line\n\n> **Since** 1.2\n\n# Returns\n\nZero.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+    }
+
+    #[test]
+    fn nested_params() {
+        const S: &str = " @param Flags The flags:\n - @c FOO enables foo,\n   which is slow.\n - @c BAR enables bar.\n\n   Other flags are ignored.\n @param Size The size.\n\n Details.";
+        const S_: &str = "# Arguments\n\n* `Flags` - The flags:\n  - `FOO` enables foo,\n    which is slow.\n  - `BAR` enables bar.\n\n    Other flags are ignored.\n* `Size` - The size.\n\nDetails.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        const C: &str =
+            "@param x Example:\n@code\nint a;\n@endcode\nmore\n```\nint b;\n```\n@param y The y.";
+        const C_: &str = "# Arguments\n\n* `x` - Example:\n\n  ```text\n  int a;\n  ```\n\n  more\n\n  ```text\n  int b;\n  ```\n* `y` - The y.";
+        assert_eq!(crate::transform(C).unwrap(), C_);
+    }
+
+    #[test]
+    fn type_params() {
+        const S: &str = "Sorts a range.\n@tparam T The element type.\n@tparam[optional] Compare The comparison.\n@param first The first element.";
+        const S_: &str = "Sorts a range.\n\n# Type Parameters\n\n* `T` - The element type.\n* `Compare` [optional]  - The comparison.\n\n# Arguments\n\n* `first` - The first element.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let doc = crate::parse(S).unwrap();
+        let names = doc.type_params().map(|param| param.name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["T", "Compare"]);
+        assert!(crate::parse("@tparam[optional").is_err());
+    }
+
+    #[test]
+    fn return_values() {
+        const S: &str = "Waits for the object.\n@return NTSTATUS Successful or errant status.\n@retval STATUS_SUCCESS The wait completed,\n or was | alerted.\n@retval STATUS_TIMEOUT The time-out elapsed.";
+        const S_: &str = "Waits for the object.\n\n# Returns\n\nNTSTATUS Successful or errant status.\n\n| Value | Description |\n| --- | --- |\n| `STATUS_SUCCESS` | The wait completed, or was \\| alerted. |\n| `STATUS_TIMEOUT` | The time-out elapsed. |";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder()
+            .return_value_style(crate::ReturnValueStyle::List)
+            .link_return_values(true)
+            .build();
+        const L_: &str = "Waits for the object.\n\n# Returns\n\nNTSTATUS Successful or errant status.\n* [`STATUS_SUCCESS`] - The wait completed,\n  or was | alerted.\n* [`STATUS_TIMEOUT`] - The time-out elapsed.";
+        assert_eq!(transformer.transform(S).unwrap(), L_);
+
+        let doc = crate::parse(S).unwrap();
+        let values = doc.return_values().map(|value| value.value.as_str());
+        assert_eq!(
+            values.collect::<Vec<_>>(),
+            ["STATUS_SUCCESS", "STATUS_TIMEOUT"]
+        );
+        assert!(matches!(
+            crate::parse("@retval\n"),
+            Err(crate::TransformError::MissingReturnValue { .. })
+        ));
+    }
+
+    #[test]
+    fn exceptions() {
+        const S: &str = "Parses the input.\n@throws std::invalid_argument If the input is malformed.\n@exception std::bad_alloc\n@return The result.";
+        const S_: &str = "Parses the input.\n\n# Exceptions\n\n* `std::invalid_argument` - If the input is malformed.\n* `std::bad_alloc` -\n\n# Returns\n\nThe result.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder()
+            .exceptions_title("Panics")
+            .link_exceptions(true)
+            .build();
+        let res = transformer
+            .transform("@exceptions Error On failure.")
+            .unwrap();
+        assert_eq!(res, "# Panics\n\n* [`Error`] - On failure.");
+        assert_eq!(crate::parse(S).unwrap().exceptions().count(), 2);
+        assert!(matches!(
+            crate::parse("@throw"),
+            Err(crate::TransformError::MissingExceptionName { .. })
+        ));
+    }
+
+    #[test]
+    fn conditions() {
+        const S: &str = "Closes a handle.\n@pre @p Handle must be valid,\n and open.\n@pre Must not be called concurrently.\n@post The handle is closed.\n@invariant The count stays positive.";
+        const S_: &str = "Closes a handle.\n\n# Safety\n\n- `Handle` must be valid,\n  and open.\n- Must not be called concurrently.\n\n# Contracts\n\n- The handle is closed.\n- The count stays positive.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder()
+            .precondition_title("Preconditions")
+            .invariant_title("Invariants")
+            .build();
+        let res = transformer.transform("@invariant A.\n@pre B.").unwrap();
+        assert_eq!(res, "# Invariants\n\n- A.\n\n# Preconditions\n\n- B.");
+
+        // indented paragraphs continue a condition
+        let res = crate::transform("@pre A\n\n  B\n@return C\n\n  D").unwrap();
+        assert_eq!(res, "# Safety\n\n- A\n\n  B\n\n# Returns\n\nC\n\nD");
+        let doc = crate::parse("@pre A\n\n  B\n@return C\n\n  D").unwrap();
+        assert_eq!(doc.blocks.len(), 2);
+
+        // blocks of a section are grouped under its first heading
+        let res = crate::transform("@post A.\n@pre B.\n@post C.").unwrap();
+        assert_eq!(res, "# Contracts\n\n- A.\n- C.\n\n# Safety\n\n- B.");
+        let res = crate::transform("@param x The x.\n@return The y.\n@param z The z.").unwrap();
+        assert_eq!(
+            res,
+            "# Arguments\n\n* `x` - The x.\n* `z` - The z.\n\n# Returns\n\nThe y."
+        );
+        let res = crate::transform("@throws A If a.\n@retval 0 On success.\n@throws B If b.");
+        assert_eq!(
+            res.unwrap(),
+            "# Exceptions\n\n* `A` - If a.\n* `B` - If b.\n\n# Returns\n\n| Value | Description |\n| --- | --- |\n| `0` | On success. |"
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        const S: &str = " Frees the buffer.\n @warning The buffer must not be used\n afterwards.\n\n   Freeing it twice is undefined behavior.\n\n Details.\n @important Call @c Init first.";
+        const S_: &str = "Frees the buffer.\n\n<div class=\"warning\">\n\n**Warning** The buffer must not be used\nafterwards.\n\nFreeing it twice is undefined behavior.\n\n</div>\n\nDetails.\n\n<div class=\"warning\">\n\n**Important** Call `Init` first.\n\n</div>";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        const C_: &str = "Frees the buffer.\n> **Warning** The buffer must not be used\n> afterwards.\n>\n> Freeing it twice is undefined behavior.\n\nDetails.\n> **Important** Call `Init` first.";
+        let transformer = crate::Transformer::builder()
+            .renderer(crate::CommonMark)
+            .build();
+        assert_eq!(transformer.transform(S).unwrap(), C_);
+    }
+
+    #[test]
+    fn sections() {
+        const S: &str = " Overview.\n @section usage Usage\n Call @ref init first, see @ref usage for details.\n @subsection usage_threads Threads\n Not thread-safe.\n @paragraph notes\n Text.";
+        const S_: &str = "Overview.\n\n## <a id=\"usage\"></a>Usage\n\nCall [`init`] first, see [Usage](#usage) for details.\n\n### <a id=\"usage_threads\"></a>Threads\n\nNot thread-safe.\n\n##### <a id=\"notes\"></a>notes\n\nText.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder()
+            .section_level(1)
+            .renderer(crate::Html)
+            .build();
+        const H_: &str =
+            "<h1 id=\"intro\">Introduction</h1>\n<p>See <a href=\"#intro\">Introduction</a></p>\n";
+        let res = transformer.transform("@section intro Introduction\nSee @ref intro");
+        assert_eq!(res.unwrap(), H_);
+    }
+
+    #[test]
+    fn titled_paragraphs() {
+        const S: &str = "Frees memory.\n@par Requirements\nWindows 10 or later,\nany edition.\n@par\nLinks against ntdll.\n\n@par Remarks\nNone.\n@note Be careful.\n@par\nReally.";
+        const S_: &str = "Frees memory.\n\n# Requirements\n\nWindows 10 or later,\nany edition.\n\nLinks against ntdll.\n\n# Remarks\n\nNone.\n> **Note** Be careful.\n>\n> Really.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        let transformer = crate::Transformer::builder()
+            .par_title_style(crate::TitleStyle::Bold)
+            .build();
+        const B_: &str = "Frees memory.\n\n**Requirements**\n\nWindows 10 or later,\nany edition.\n\nLinks against ntdll.\n\n**Remarks**\n\nNone.\n> **Note** Be careful.\n>\n> Really.";
+        assert_eq!(transformer.transform(S).unwrap(), B_);
+    }
+
+    #[test]
+    fn conditional_sections() {
+        const S: &str = "Opens a file.\n@cond INTERNAL\n@param Internal Reserved.\n@endcond\n@param Name The name, on @if WIN64 x64 @elseif (ARM64 && !WIN32) arm64 @else x86 @endif systems.\n@ifnot WIN64\n@note Not 64-bit.\n@endif";
+        let transform = |labels: &[&str]| {
+            let builder = labels
+                .iter()
+                .fold(crate::Transformer::builder(), |builder, label| {
+                    builder.enable_section(*label)
+                });
+            builder.build().transform(S).unwrap()
+        };
+        assert_eq!(
+            transform(&[]),
+            "Opens a file.\n\n# Arguments\n\n* `Name` - The name, on x86 systems.\n> **Note** Not 64-bit."
+        );
+        assert_eq!(
+            transform(&["WIN64", "INTERNAL"]),
+            "Opens a file.\n\n# Arguments\n\n* `Internal` - Reserved.\n* `Name` - The name, on x64 systems."
+        );
+        assert_eq!(
+            transform(&["ARM64"]),
+            "Opens a file.\n\n# Arguments\n\n* `Name` - The name, on arm64 systems.\n> **Note** Not 64-bit."
+        );
+
+        let err = crate::transform("Text.\n@if (A &&").unwrap_err();
+        assert!(matches!(
+            err,
+            crate::TransformError::MalformedCondition { .. }
+        ));
+        let err = crate::transform("Text.\n@if A\n@if B\n@endif").unwrap_err();
+        assert!(
+            matches!(&err, crate::TransformError::UnterminatedBlock { command, .. } if command == "if")
+        );
+        assert_eq!(err.location().offset, 6);
+
+        // in lenient mode, a malformed expression is kept as text
+        let (res, diagnostics) =
+            crate::transform_lenient("Text.\n@if (A && B\nImportant details.\n@param x The x.");
+        assert_eq!(
+            res,
+            "Text.\n@if (A && B\nImportant details.\n\n# Arguments\n\n* `x` - The x."
+        );
+        assert_eq!(diagnostics.len(), 1);
+
+        // as is an unclosed condition
+        let (res, diagnostics) = crate::transform_lenient("Text\n@cond X\nmore\n@param y Y");
+        assert_eq!(res, "Text\n@cond X\nmore\n\n# Arguments\n\n* `y` - Y");
+        assert!(
+            matches!(&diagnostics[..], [crate::Diagnostic { error: crate::TransformError::UnterminatedBlock { command, .. }, .. }] if command == "cond")
+        );
+        let (res, _) = crate::transform_lenient("@if A\nText\n@if B\nb\n@endif\nmore");
+        assert_eq!(res, "@if A\nText\nmore");
+    }
+
+    #[test]
+    fn internal_docs() {
+        const S: &str = "Opens a file.\n@internal\nUses the cache.\n@endinternal\n@param Name The name.\n@internal\n@param Flags Reserved.";
+        // public text is left, so the item is not internal
+        assert!(!crate::parse(S).unwrap().internal);
+        assert_eq!(
+            crate::transform(S).unwrap(),
+            "Opens a file.\n\n# Arguments\n\n* `Name` - The name."
+        );
+        let transformer = crate::Transformer::builder().keep_internal(true).build();
+        assert_eq!(
+            transformer.transform(S).unwrap(),
+            "Opens a file.\nUses the cache.\n\n# Arguments\n\n* `Name` - The name.\n* `Flags` - Reserved."
+        );
+
+        assert!(crate::parse("@private Opens a file.").unwrap().internal);
+        assert_eq!(
+            crate::transform("@private Opens a file.").unwrap(),
+            "Opens a file."
+        );
+        assert!(!crate::parse("Opens a file.").unwrap().internal);
+        assert!(
+            crate::parse("@internal\nReserved.\n@param x The x.")
+                .unwrap()
+                .internal
+        );
+        assert!(
+            !crate::parse("@internal Reserved. @endinternal\nOpens a file.")
+                .unwrap()
+                .internal
+        );
+    }
+
+    #[test]
+    fn format_blocks() {
+        const S: &str = "Computes @htmlonly<var>x</var>@endhtmlonly squared @latexonly $x^2$ @endlatexonly by multiplication.\n@htmlonly[block]\n  <table><tr><td>x</td></tr></table>\n@endhtmlonly\n@manonly\n.B x\n@endmanonly\n@rtfonly {\\b x} @endrtfonly\nDone.";
+        assert_eq!(
+            crate::transform(S).unwrap(),
+            "Computes <var>x</var> squared by multiplication.\n\n<table><tr><td>x</td></tr></table>\n\nDone."
+        );
+        assert_eq!(
+            crate::Transformer::builder()
+                .renderer(crate::PlainText)
+                .build()
+                .transform(S)
+                .unwrap(),
+            "Computes  squared by multiplication.\n\nDone."
+        );
+
+        let err = crate::transform("Text.\n@xmlonly <x/>").unwrap_err();
+        assert!(
+            matches!(&err, crate::TransformError::UnterminatedBlock { command, .. } if command == "xmlonly")
+        );
+    }
+
+    #[test]
+    fn links() {
+        const S: &str = "@anchor open Opens a file with @link OpenOptions the given\n  options @endlink, see {@link File#open(String) opening files} and {@linkplain Close}.\nReturns {@code {0}} on success, see @link https://example.com the docs@endlink.";
+        assert_eq!(
+            crate::transform(S).unwrap(),
+            "<a id=\"open\"></a>Opens a file with [the given options](OpenOptions), see [opening files](File::open()) and [`Close`].\nReturns `{0}` on success, see [the docs](https://example.com)."
+        );
+        let transformer = crate::Transformer::builder()
+            .renderer(crate::PlainText)
+            .build();
+        assert_eq!(
+            transformer.transform(S).unwrap(),
+            "Opens a file with the given options, see opening files and Close. Returns {0} on success, see the docs."
+        );
+
+        let res = crate::transform("Uses {@code a`b} and {@code `c}.").unwrap();
+        assert_eq!(res, "Uses ``a`b`` and `` `c ``.");
+
+        let err = crate::transform("Opens a @link File file.").unwrap_err();
+        assert!(
+            matches!(&err, crate::TransformError::UnterminatedBlock { command, .. } if command == "link")
+        );
+    }
+
+    #[test]
+    fn ref_text() {
+        const S: &str = "Calls @ref FOO \"the foo routine\" or @ref BAR instead, see @ref https://example.com \"the docs\" and @ref usage \"\"\n@section usage Usage";
+        assert_eq!(
+            crate::transform(S).unwrap(),
+            "Calls [the foo routine](FOO) or [`BAR`] instead, see [the docs](https://example.com) and [Usage](#usage)\n\n## <a id=\"usage\"></a>Usage"
+        );
+
+        // punctuation ending the sentence is not part of the target
+        const P: &str =
+            "See @ref Foo, @ref Bar. (Or @ref baz().) Then @ref usage.\n@section usage Usage";
+        const P_: &str = "See [`Foo`], [`Bar`]. (Or [`baz()`].) Then [Usage](#usage).\n\n## <a id=\"usage\"></a>Usage";
+        assert_eq!(crate::transform(P).unwrap(), P_);
+    }
+
+    #[test]
+    fn see_also() {
+        const S: &str = "Queries a file.\n@see NtQueryInformationFile, NtSetInformationFile, ZwQueryInformationFile for\nthe kernel-mode variant.\n@see the @c FILE_INFORMATION_CLASS documentation\n@sa fopen()";
+        const S_: &str = "Queries a file.\n\n# See also\n\n- [`NtQueryInformationFile`]\n- [`NtSetInformationFile`]\n- [`ZwQueryInformationFile`] for\n  the kernel-mode variant.\n- the `FILE_INFORMATION_CLASS` documentation\n- [`fopen()`]";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+        let doc = crate::parse(S).unwrap();
+        let targets = doc.see_also().map(|reference| reference.target.as_str());
+        assert_eq!(
+            targets.collect::<Vec<_>>(),
+            [
+                "NtQueryInformationFile",
+                "NtSetInformationFile",
+                "ZwQueryInformationFile",
+                "fopen()"
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_lists() {
+        const S: &str = "Opens a file.\n - Modes:\n   -# Read\n   -# Write\n      * Truncates\n   5. Append\n - Flags are optional.\n@param Options The options:\n @li @c OPEN_EXISTING\n @arg @c CREATE_NEW\n    @arg Fails if present.";
+        const S_: &str = "Opens a file.\n- Modes:\n  1. Read\n  2. Write\n     - Truncates\n  3. Append\n- Flags are optional.\n\n# Arguments\n\n* `Options` - The options:\n  - `OPEN_EXISTING`\n  - `CREATE_NEW`\n    - Fails if present.";
+        assert_eq!(crate::transform(S).unwrap(), S_);
+
+        assert_eq!(
+            crate::transform("- a\n\n- b\n\n  para of b").unwrap(),
+            "- a\n- b\n\n  para of b"
+        );
+
+        // numbered lists only interrupt a paragraph at 1
+        const T: &str =
+            "Supported since Windows\n10. Earlier versions fail.\nSteps:\n1. Open\n7. Close";
+        const T_: &str =
+            "Supported since Windows\n10. Earlier versions fail.\nSteps:\n1. Open\n2. Close";
+        assert_eq!(crate::transform(T).unwrap(), T_);
+
+        // lists directly following a note or condition are part of it
+        let res = crate::transform("@remarks Details:\n - first\n - second").unwrap();
+        assert_eq!(res, "> Details:\n> - first\n> - second");
+        let res = crate::transform("@pre x:\n- a\n- b\n@return y:\n- c").unwrap();
+        assert_eq!(
+            res,
+            "# Safety\n\n- x:\n  - a\n  - b\n\n# Returns\n\ny:\n- c"
+        );
+
+        let transformer = crate::Transformer::builder().renderer(crate::Html).build();
+        let res = transformer.transform("-# One\n-# Two").unwrap();
+        assert_eq!(
+            res,
+            "<ol>\n<li><p>One</p>\n</li>\n<li><p>Two</p>\n</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn convert_html() {
+        const S: &str = "Set encoding parameters to default values:\n<ul>\n<li>Lossless</li>\n<li>1 tile\n<ol><li>Tiled</li></ol>\n</li>\n<li>etc...</li>\n</ul>\n@param parameters Compression parameters";
+        const S_: &str = "Set encoding parameters to default values:\n- Lossless\n- 1 tile\n  1. Tiled\n- etc...\n\n# Arguments\n\n* `parameters` - Compression parameters";
+        let transformer = crate::Transformer::builder().convert_html(true).build();
+        assert_eq!(transformer.transform(S).unwrap(), S_);
+        let res = transformer.transform("<code>a`b</code>");
+        assert_eq!(res.unwrap(), "``a`b``");
+        let res = transformer.transform("<ul><li>A<p>B</p></li><li>C</li></ul>");
+        assert_eq!(res.unwrap(), "- A\n\n  B\n- C");
+
+        const T: &str = "Returns <b>true</b> if <I>x</I> is <tt>a &lt; b</tt>.<br>\nSee <a href=\"https://example.com\">the docs</a>, <sup>2</sup>, <b>@c x</b>, <stdio.h> and </div>.<p>Next.\n<pre>\n  int x = 1 &amp; 2;\n</pre>";
+        const T_: &str = "Returns **true** if _x_ is `a < b`.\\\nSee [the docs](https://example.com), <sup>2</sup>, <b>`x`</b>, &lt;stdio.h> and &lt;/div>.\n\nNext.\n\n```text\nint x = 1 & 2;\n```";
+        assert_eq!(transformer.transform(T).unwrap(), T_);
+        assert_eq!(
+            crate::transform("<b>true</b> <x>").unwrap(),
+            "<b>true</b> <x>"
+        );
+        assert_eq!(
+            transformer
+                .transform("Café uses <stdio.h>, Résumé < 5")
+                .unwrap(),
+            "Café uses &lt;stdio.h>, Résumé < 5"
+        );
+    }
 }
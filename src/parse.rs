@@ -0,0 +1,1590 @@
+//! Parsing of Doxygen comments into a [`DocComment`].
+
+use crate::ast::{
+    Block, ConditionKind, DocComment, Exception, Inline, NoteKind, Param, Reference, ReturnValue,
+    SeeAlso,
+};
+use crate::condition::evaluate;
+use crate::error::{Diagnostic, Location, Severity, TransformError};
+use crate::tags::{self, decode_entities, find_end};
+use crate::transformer::Options;
+use yap::types::{StrTokens, StrTokensLocation};
+use yap::{IntoTokens, TokenLocation, Tokens};
+
+const SEPS: [char; 5] = [' ', '\t', '\r', '\n', '['];
+
+/// Commands that start a new block, even in the middle of a line.
+const BLOCK_COMMANDS: &[&str] = &[
+    "brief",
+    "short",
+    "param",
+    "tparam",
+    "returns",
+    "return",
+    "result",
+    "retval",
+    "throw",
+    "throws",
+    "exception",
+    "exceptions",
+    "pre",
+    "post",
+    "invariant",
+    "see",
+    "sa",
+    "note",
+    "since",
+    "deprecated",
+    "remark",
+    "remarks",
+    "warning",
+    "attention",
+    "important",
+    "li",
+    "arg",
+    "par",
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+    "code",
+    "verbatim",
+];
+
+/// Commands that include or exclude text depending on the enabled sections.
+const CONDITIONAL_COMMANDS: &[&str] = &[
+    "cond",
+    "endcond",
+    "if",
+    "ifnot",
+    "else",
+    "elseif",
+    "endif",
+    "internal",
+    "endinternal",
+];
+
+/// Commands whose content is only included in a specific output format, up to their end
+/// command.
+const FORMAT_COMMANDS: &[&str] = &[
+    "htmlonly",
+    "latexonly",
+    "manonly",
+    "rtfonly",
+    "xmlonly",
+    "docbookonly",
+];
+
+/// Elements converted into inline markdown when they contain plain text only.
+const INLINE_TAGS: &[&str] = &["b", "strong", "i", "em", "tt", "code", "a"];
+
+/// Characters that can be escaped with a backslash or an at sign.
+const ESCAPES: [char; 10] = ['\\', '@', '&', '$', '#', '<', '>', '%', '"', '.'];
+
+/// Returns the name of the command at the start of `str`, if any.
+fn command_name(str: &str) -> Option<&str> {
+    let rest = str.strip_prefix(['@', '\\'])?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    Some(&rest[..end]).filter(|name| !name.is_empty())
+}
+
+/// Returns the length of the list marker at the start of `str`, if any.
+fn list_marker(str: &str) -> Option<(usize, bool)> {
+    let digits = str.chars().take_while(char::is_ascii_digit).count();
+    let (len, ordered) = match str.chars().next()? {
+        '-' if str.starts_with("-#") => (2, true),
+        '-' | '*' | '+' => (1, false),
+        _ if digits > 0 && str[digits..].starts_with('.') => (digits + 1, true),
+        _ => return None,
+    };
+    str[len..]
+        .starts_with([' ', '\t'])
+        .then_some((len + 1, ordered))
+}
+
+/// Strips blank leading and trailing lines and the common indentation of a code block.
+fn dedent(code: &str) -> String {
+    let lines = code.lines().map(str::trim_end).collect::<Vec<_>>();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return String::new();
+    };
+    let lines = &lines[first..=last];
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    let lines = lines.iter().map(|line| line.get(indent..).unwrap_or(""));
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Returns the width of the indentation of `line`, counting tabs as four columns.
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|&c| c == ' ' || c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Returns the fence character and length of the markdown code fence opening `line`, if any.
+fn code_fence(line: &str) -> Option<(char, usize)> {
+    let line = line.trim_start_matches([' ', '\t']);
+    let fence = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = line.chars().take_while(|&c| c == fence).count();
+    Some((fence, len)).filter(|_| len >= 3)
+}
+
+/// Returns the description of the last entry of a block of parameters or return values.
+fn entry_description(block: &mut Block) -> Option<&mut Vec<Block>> {
+    match block {
+        Block::Params(params) | Block::TypeParams(params) => {
+            params.last_mut().map(|param| &mut param.description)
+        }
+        Block::ReturnValues(values) => values.last_mut().map(|value| &mut value.description),
+        Block::Exceptions(exceptions) => exceptions
+            .last_mut()
+            .map(|exception| &mut exception.description),
+        Block::SeeAlso(entries) => entries.last_mut().map(|entry| &mut entry.description),
+        _ => None,
+    }
+}
+
+/// Returns whether `block` is a list of described entries, which continuation lines belong to.
+fn has_entries(block: Option<&Block>) -> bool {
+    matches!(
+        block,
+        Some(
+            Block::Params(_)
+                | Block::TypeParams(_)
+                | Block::ReturnValues(_)
+                | Block::Exceptions(_)
+                | Block::SeeAlso(_)
+        )
+    )
+}
+
+/// Returns the content of the open note, condition, return description or entry at the end of
+/// `blocks`, which lists nest in, or `blocks` itself.
+fn open_content(blocks: &mut Vec<Block>) -> &mut Vec<Block> {
+    let nested = matches!(
+        blocks.last(),
+        Some(Block::Note { .. } | Block::Conditions { .. } | Block::Returns(_))
+    ) || has_entries(blocks.last());
+    if !nested {
+        return blocks;
+    }
+    match blocks.last_mut() {
+        Some(Block::Note { content, .. } | Block::Returns(content)) => content,
+        Some(Block::Conditions { items, .. }) => items.last_mut().expect("list without items"),
+        Some(block) => entry_description(block).expect("empty list of entries"),
+        None => unreachable!(),
+    }
+}
+
+/// Returns the blocks of the last item at `depth` levels of list nesting within `blocks`, or
+/// `blocks` itself at depth zero.
+fn nested_list(blocks: &mut Vec<Block>, depth: usize) -> &mut Vec<Block> {
+    if depth == 0 || !matches!(blocks.last(), Some(Block::List { .. })) {
+        return blocks;
+    }
+    match blocks.last_mut() {
+        Some(Block::List { items, .. }) => {
+            nested_list(items.last_mut().expect("list without items"), depth - 1)
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the paragraph text is appended to within `content`, descending into nested lists.
+fn open_paragraph(content: &mut Vec<Block>) -> &mut Vec<Inline> {
+    if !matches!(
+        content.last(),
+        Some(Block::Paragraph(_) | Block::List { .. })
+    ) {
+        content.push(Block::Paragraph(vec![]));
+    }
+    match content.last_mut() {
+        Some(Block::Paragraph(inlines)) => inlines,
+        Some(Block::List { items, .. }) => {
+            open_paragraph(items.last_mut().expect("list without items"))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Extracts the next word token.
+fn take_word(toks: &mut impl Tokens<Item = char>) -> String {
+    toks.take_while(|&c| !SEPS.into_iter().any(|s| c == s))
+        .collect::<String>()
+}
+
+/// Returns whether the first word of a `@see` entry followed by more text names an item or URL,
+/// e.g. `NtClose` or `fopen()`, rather than starting a sentence such as "the documentation".
+fn is_reference(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next();
+    chars.any(|c| c.is_ascii_uppercase())
+        || word.contains("://")
+        || word
+            .chars()
+            .any(|c| c.is_ascii_digit() || matches!(c, '_' | ':' | '(' | '#'))
+}
+
+/// Returns the name of the Javadoc inline tag at the start of `str`, such as `link` for
+/// `{@link Foo}`, if any.
+fn javadoc_tag(str: &str) -> Option<&'static str> {
+    let rest = str.strip_prefix("{@")?;
+    ["linkplain", "link", "code", "literal"]
+        .into_iter()
+        .find(|tag| {
+            rest.strip_prefix(tag)
+                .is_some_and(|rest| rest.starts_with([' ', '\t', '}']))
+        })
+}
+
+/// Converts a Javadoc link target such as `Foo#bar(int)` into a path rustdoc resolves.
+fn javadoc_target(target: &str) -> String {
+    let target = target.trim_start_matches('#').replace('#', "::");
+    match target.split_once('(') {
+        Some((name, _)) => format!("{name}()"),
+        None => target,
+    }
+}
+
+/// Returns the target of a `@ref` without the punctuation that ends the sentence around it, e.g.
+/// in `see @ref Foo.` or `(@ref Bar)`, keeping the parentheses of a function such as `foo()`.
+fn ref_target(word: &str) -> &str {
+    let mut target = word;
+    loop {
+        let unbalanced = target.matches(')').count() > target.matches('(').count();
+        match target.chars().next_back() {
+            Some('.' | ',' | ';' | ':') => {}
+            Some(')') if unbalanced => {}
+            _ => return target,
+        }
+        target = &target[..target.len() - 1];
+    }
+}
+
+/// Returns the text of a link with collapsed whitespace, if any.
+fn link_text(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Skips whitespace tokens on the current line.
+fn skip_blanks(toks: &mut impl Tokens<Item = char>) {
+    toks.skip_while(|&c| c == ' ' || c == '\t' || c == '\r');
+}
+
+/// Parses a Doxygen comment into a [`DocComment`].
+pub fn parse(str: &str) -> Result<DocComment, TransformError> {
+    parse_with(str, &Options::default())
+}
+
+/// Parses a Doxygen comment, keeping malformed commands as text instead of failing.
+pub fn parse_lenient(str: &str) -> (DocComment, Vec<Diagnostic>) {
+    parse_lenient_with(str, &Options::default())
+}
+
+/// Parses a Doxygen comment, with the conditional sections enabled in `options`.
+pub(crate) fn parse_with(str: &str, options: &Options) -> Result<DocComment, TransformError> {
+    let mut parser = Parser::new(str, options, false);
+    parser.parse()?;
+    Ok(parser.finish())
+}
+
+/// Parses a Doxygen comment leniently, with the conditional sections enabled in `options`.
+pub(crate) fn parse_lenient_with(str: &str, options: &Options) -> (DocComment, Vec<Diagnostic>) {
+    let mut parser = Parser::new(str, options, true);
+    parser.parse().expect("lenient parsing never fails");
+    let diagnostics = std::mem::take(&mut parser.diagnostics);
+    (parser.finish(), diagnostics)
+}
+
+/// Removes paragraphs left empty by commands without any text.
+fn prune(blocks: &mut Vec<Block>) {
+    blocks.retain(|block| !matches!(block, Block::Paragraph(inlines) if inlines.is_empty()));
+    for block in blocks {
+        match block {
+            Block::List { items, .. } | Block::Conditions { items, .. } => {
+                items.iter_mut().for_each(prune)
+            }
+            Block::Note { content, .. }
+            | Block::TitledParagraph { content, .. }
+            | Block::Returns(content) => prune(content),
+            Block::Params(params) | Block::TypeParams(params) => {
+                params.iter_mut().for_each(|p| prune(&mut p.description))
+            }
+            Block::ReturnValues(values) => {
+                values.iter_mut().for_each(|v| prune(&mut v.description))
+            }
+            Block::Exceptions(exceptions) => exceptions
+                .iter_mut()
+                .for_each(|e| prune(&mut e.description)),
+            _ => {}
+        }
+    }
+}
+
+/// An open `@if`, `@cond` or `@internal` section.
+struct Condition {
+    command: &'static str,
+    /// Byte offset of the command.
+    start: usize,
+    /// Whether text in the current branch is included.
+    active: bool,
+    /// Whether text around the section is included.
+    parent: bool,
+    /// Whether a branch of an `@if` was included already.
+    taken: bool,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    toks: StrTokens<'a>,
+    options: &'a Options,
+    /// Whether errors are recorded as diagnostics rather than returned.
+    lenient: bool,
+    diagnostics: Vec<Diagnostic>,
+    blocks: Vec<Block>,
+    /// Whether the last block still accepts text.
+    open: bool,
+    /// Whether a line ended since text was last appended.
+    line_break: bool,
+    /// Whether text is appended to the title of a `@par` rather than its content.
+    title: bool,
+    /// Indentation shared by all lines, which indented code blocks are relative to.
+    indent: usize,
+    /// Open conditional sections, innermost last.
+    conditions: Vec<Condition>,
+    /// Whether `@private` occurred.
+    private: bool,
+    /// Whether `@internal` occurred.
+    internal: bool,
+    /// Whether text outside of `@internal` sections was kept.
+    public: bool,
+    /// Indentation of the item markers of the open list and its nested lists, outermost first.
+    list_indents: Vec<usize>,
+    /// Whether each open HTML list is ordered, outermost first.
+    html_lists: Vec<bool>,
+    /// Names of the HTML elements kept as is, whose end tag is yet to come.
+    html_open: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, options: &'a Options, lenient: bool) -> Self {
+        Parser {
+            source,
+            toks: source.into_tokens(),
+            options,
+            lenient,
+            diagnostics: vec![],
+            blocks: vec![],
+            open: false,
+            line_break: false,
+            title: false,
+            indent: source
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(indentation)
+                .min()
+                .unwrap_or(0),
+            conditions: vec![],
+            private: false,
+            internal: false,
+            public: false,
+            list_indents: vec![],
+            html_lists: vec![],
+            html_open: vec![],
+        }
+    }
+
+    fn finish(mut self) -> DocComment {
+        prune(&mut self.blocks);
+        DocComment {
+            blocks: self.blocks,
+            internal: self.private || self.internal && !self.public,
+        }
+    }
+
+    fn parse(&mut self) -> Result<(), TransformError> {
+        loop {
+            let column = indentation(self.toks.remaining());
+            if let Some(block) = self.parse_markdown_code() {
+                self.push_code(block, Some(column));
+                continue;
+            }
+            skip_blanks(&mut self.toks);
+            match self.toks.peek() {
+                None => return self.finish_conditions(),
+                Some('\n') => {
+                    // a blank line ends the current block, unless an indented line continues a
+                    // note, description or list item
+                    self.toks.next();
+                    self.open = self.open && self.continues_description();
+                    if self.open {
+                        self.continue_paragraph();
+                    }
+                }
+                Some(_) => self.parse_line()?,
+            }
+        }
+    }
+
+    /// Parses the remainder of a line, or up to the next block command.
+    fn parse_line(&mut self) -> Result<(), TransformError> {
+        if let Some(name) = self.block_command() {
+            let start = self.toks.location();
+            self.toks.take(name.len() + 1).consume();
+            skip_blanks(&mut self.toks);
+            match self.parse_block_command(name, start) {
+                Ok(()) => self.line_break = false,
+                Err(err) => self.recover(err, start, name)?,
+            }
+        } else if let Some((len, ordered)) = list_marker(self.toks.remaining())
+            && self.starts_list_item(len)
+        {
+            self.line_break = false;
+            let column = self.column(self.toks.offset());
+            self.toks.take(len).consume();
+            skip_blanks(&mut self.toks);
+            self.push_list_item(ordered, column);
+        }
+        self.parse_inlines()?;
+        // titles end with the line
+        self.title = false;
+        if matches!(self.blocks.last(), Some(Block::Section { .. })) {
+            self.open = false;
+        }
+        Ok(())
+    }
+
+    /// Keeps a malformed block command as text, unless parsing strictly.
+    fn recover(
+        &mut self,
+        err: TransformError,
+        start: StrTokensLocation,
+        name: &str,
+    ) -> Result<(), TransformError> {
+        if !self.lenient {
+            return Err(err);
+        }
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            error: err,
+        });
+        self.toks.set_location(start);
+        let command = self.toks.take(name.len() + 1).collect::<String>();
+        self.push_inline(Inline::Text(command));
+        Ok(())
+    }
+
+    /// Returns the name of the block command at the current position, if any.
+    fn block_command(&self) -> Option<&'static str> {
+        let name = command_name(self.toks.remaining())?;
+        BLOCK_COMMANDS.iter().copied().find(|&cmd| cmd == name)
+    }
+
+    fn parse_block_command(
+        &mut self,
+        name: &str,
+        start: StrTokensLocation,
+    ) -> Result<(), TransformError> {
+        match name {
+            "param" => {
+                let param = self.parse_param()?;
+                match self.blocks.last_mut() {
+                    Some(Block::Params(params)) => params.push(param),
+                    _ => self.blocks.push(Block::Params(vec![param])),
+                }
+            }
+            "tparam" => {
+                let param = self.parse_param()?;
+                match self.blocks.last_mut() {
+                    Some(Block::TypeParams(params)) => params.push(param),
+                    _ => self.blocks.push(Block::TypeParams(vec![param])),
+                }
+            }
+            "retval" => {
+                let value = take_word(&mut self.toks);
+                if value.is_empty() {
+                    return Err(TransformError::MissingReturnValue {
+                        location: self.location(),
+                    });
+                }
+                skip_blanks(&mut self.toks);
+                let value = ReturnValue {
+                    value,
+                    ..ReturnValue::default()
+                };
+                match self.blocks.last_mut() {
+                    Some(Block::ReturnValues(values)) => values.push(value),
+                    _ => self.blocks.push(Block::ReturnValues(vec![value])),
+                }
+            }
+            "throw" | "throws" | "exception" | "exceptions" => {
+                let name = take_word(&mut self.toks);
+                if name.is_empty() {
+                    return Err(TransformError::MissingExceptionName {
+                        location: self.location(),
+                    });
+                }
+                skip_blanks(&mut self.toks);
+                let exception = Exception {
+                    name,
+                    ..Exception::default()
+                };
+                match self.blocks.last_mut() {
+                    Some(Block::Exceptions(exceptions)) => exceptions.push(exception),
+                    _ => self.blocks.push(Block::Exceptions(vec![exception])),
+                }
+            }
+            "pre" | "post" | "invariant" => {
+                let kind = match name {
+                    "pre" => ConditionKind::Pre,
+                    "post" => ConditionKind::Post,
+                    _ => ConditionKind::Invariant,
+                };
+                match self.blocks.last_mut() {
+                    Some(Block::Conditions { kind: last, items }) if *last == kind => {
+                        items.push(vec![])
+                    }
+                    _ => self.blocks.push(Block::Conditions {
+                        kind,
+                        items: vec![vec![]],
+                    }),
+                }
+            }
+            "returns" | "return" | "result" => match self.blocks.last_mut() {
+                Some(Block::Returns(content)) => content.push(Block::Paragraph(vec![])),
+                _ => self.blocks.push(Block::Returns(vec![])),
+            },
+            "see" | "sa" => {
+                let entries = self.parse_see_also();
+                match self.blocks.last_mut() {
+                    Some(Block::SeeAlso(last)) => last.extend(entries),
+                    _ => self.blocks.push(Block::SeeAlso(entries)),
+                }
+            }
+            "note" | "since" | "deprecated" | "remark" | "remarks" | "warning" | "attention"
+            | "important" => {
+                let kind = match name {
+                    "note" => NoteKind::Note,
+                    "since" => NoteKind::Since,
+                    "deprecated" => NoteKind::Deprecated,
+                    "warning" => NoteKind::Warning,
+                    "attention" => NoteKind::Attention,
+                    "important" => NoteKind::Important,
+                    _ => NoteKind::Remark,
+                };
+                self.blocks.push(Block::Note {
+                    kind,
+                    content: vec![],
+                });
+            }
+            "code" | "verbatim" => {
+                let block = self.parse_code_block(name, start)?;
+                self.push_code(block, self.column(start.offset()));
+                return Ok(());
+            }
+            "li" | "arg" => self.push_list_item(false, self.column(start.offset())),
+            "section" | "subsection" | "subsubsection" | "paragraph" => {
+                let level = match name {
+                    "section" => 1,
+                    "subsection" => 2,
+                    "subsubsection" => 3,
+                    _ => 4,
+                };
+                let anchor = take_word(&mut self.toks);
+                skip_blanks(&mut self.toks);
+                let mut title = vec![];
+                if matches!(self.toks.peek(), None | Some('\n')) && !anchor.is_empty() {
+                    // without a title, the section is named after its anchor
+                    title.push(Inline::Text(anchor.clone()));
+                }
+                self.blocks.push(Block::Section {
+                    level,
+                    anchor,
+                    title,
+                });
+            }
+            "par" if matches!(self.toks.peek(), None | Some('\n')) => self.new_paragraph(),
+            "par" => {
+                self.blocks.push(Block::TitledParagraph {
+                    title: vec![],
+                    content: vec![],
+                });
+                self.title = true;
+            }
+            _ => self.blocks.push(Block::Paragraph(vec![])),
+        }
+        self.open = true;
+        Ok(())
+    }
+
+    /// Starts a new paragraph, which continues the previous block if possible.
+    fn new_paragraph(&mut self) {
+        // a paragraph within an HTML list item stays in the item
+        if self.open && !self.html_lists.is_empty() {
+            let content = open_content(&mut self.blocks);
+            nested_list(content, self.html_lists.len()).push(Block::Paragraph(vec![]));
+            return;
+        }
+        let content = match self.blocks.last_mut() {
+            Some(Block::TitledParagraph { content, .. }) => Some(content),
+            Some(Block::Note { content, .. } | Block::Returns(content)) if self.open => {
+                Some(content)
+            }
+            Some(block) if self.open => entry_description(block),
+            _ => None,
+        };
+        match content {
+            Some(content) => content.push(Block::Paragraph(vec![])),
+            None => self.blocks.push(Block::Paragraph(vec![])),
+        }
+    }
+
+    /// Parses the comma-separated references of a `@see` command, where the last entry may
+    /// continue with a description and an entry that does not start with a reference is free
+    /// text.
+    fn parse_see_also(&mut self) -> Vec<SeeAlso> {
+        let mut entries = vec![];
+        loop {
+            if matches!(self.toks.peek(), None | Some('\n')) || self.block_command().is_some() {
+                if entries.is_empty() {
+                    entries.push(SeeAlso::default());
+                }
+                break;
+            }
+            let start = self.toks.location();
+            let word = take_word(&mut self.toks);
+            let listed = word.ends_with(',');
+            let target = word.trim_end_matches(',');
+            skip_blanks(&mut self.toks);
+            let last =
+                matches!(self.toks.peek(), None | Some('\n')) || self.block_command().is_some();
+            let command = target.starts_with(['@', '\\']);
+            if target.is_empty()
+                || command
+                || !(listed || last || !entries.is_empty() || is_reference(target))
+            {
+                self.toks.set_location(start);
+                entries.push(SeeAlso::default());
+                break;
+            }
+            entries.push(SeeAlso {
+                reference: Some(Reference {
+                    target: target.to_owned(),
+                    text: None,
+                }),
+                description: vec![],
+            });
+            if !listed {
+                break;
+            }
+        }
+        entries
+    }
+
+    /// Parses the language and content of a code block, up to its closing command.
+    fn parse_code_block(
+        &mut self,
+        name: &str,
+        start: StrTokensLocation,
+    ) -> Result<Block, TransformError> {
+        let language = match name {
+            "verbatim" => Some("text".to_owned()),
+            _ if self.toks.token('{') => {
+                let language = self
+                    .toks
+                    .take_while(|&c| c != '}' && c != '\n')
+                    .collect::<String>();
+                self.toks.token('}');
+                Some(language.trim().trim_start_matches('.').to_owned())
+                    .filter(|language| !language.is_empty())
+            }
+            _ => None,
+        };
+        let code = dedent(&self.take_until_end(name, start)?);
+        Ok(Block::Code { language, code })
+    }
+
+    /// Takes the raw content of a block up to its closing command, e.g. `@endcode`.
+    fn take_until_end(
+        &mut self,
+        name: &str,
+        start: StrTokensLocation,
+    ) -> Result<String, TransformError> {
+        let rest = self.toks.remaining();
+        let end = ['@', '\\']
+            .into_iter()
+            .filter_map(|c| rest.find(&format!("{c}end{name}")))
+            .min()
+            .ok_or_else(|| TransformError::UnterminatedBlock {
+                command: name.to_owned(),
+                location: Location::new(self.source, start.offset()),
+            })?;
+        let content = rest[..end].to_owned();
+        let len = rest[..end].chars().count() + "@end".len() + name.len();
+        self.toks.take(len).consume();
+        Ok(content)
+    }
+
+    /// Adds a code or HTML block starting at `column`, which stays in the open note, condition,
+    /// entry or list item it is indented below.
+    fn push_code(&mut self, block: Block, column: Option<usize>) {
+        if self.conditions.iter().all(|c| c.command != "internal") {
+            self.public = true;
+        }
+        let depth = match column {
+            Some(column) => self
+                .list_indents
+                .iter()
+                .filter(|&&indent| indent < column)
+                .count(),
+            // a block in the middle of a line continues the innermost list item
+            None => self.list_indents.len(),
+        };
+        let last = self.blocks.last();
+        let contained = matches!(
+            last,
+            Some(Block::Note { .. } | Block::Conditions { .. } | Block::Returns(_))
+        ) || has_entries(last);
+        let list = matches!(last, Some(Block::List { .. }));
+        if self.open && (contained || list && depth > 0) {
+            nested_list(open_content(&mut self.blocks), depth).push(block);
+        } else {
+            self.blocks.push(block);
+            self.open = false;
+        }
+    }
+
+    /// Parses a fenced or indented markdown code block starting at the current line, if any.
+    fn parse_markdown_code(&mut self) -> Option<Block> {
+        let consumed = self.toks.consumed();
+        if !consumed.is_empty() && !consumed.ends_with('\n') {
+            return None;
+        }
+        let rest = self.toks.remaining();
+        let (len, block) = if let Some((fence, fence_len)) = code_fence(rest) {
+            let (first, body) = rest.split_once('\n').unwrap_or((rest, ""));
+            let info = first.trim().trim_start_matches(fence).trim();
+            if fence == '`' && info.contains('`') {
+                return None;
+            }
+            // an unclosed fence runs to the end of the comment
+            let mut end = body.len();
+            let mut len = rest.len();
+            let mut offset = 0;
+            for line in body.split_inclusive('\n') {
+                if code_fence(line).is_some_and(|(c, n)| c == fence && n >= fence_len)
+                    && line.trim().trim_start_matches(fence).is_empty()
+                {
+                    end = offset;
+                    len = first.len() + 1 + offset + line.len();
+                    break;
+                }
+                offset += line.len();
+            }
+            let language = info
+                .trim_start_matches('{')
+                .trim_end_matches('}')
+                .trim_start_matches('.')
+                .split_whitespace()
+                .next()
+                .map(str::to_owned);
+            let code = dedent(&body[..end]);
+            (len, Block::Code { language, code })
+        } else {
+            // indented code may not follow text directly, and continues a list item otherwise
+            let list = matches!(self.blocks.last(), Some(Block::List { .. }))
+                || has_entries(self.blocks.last());
+            let command =
+                command_name(rest.trim_start()).is_some_and(|name| BLOCK_COMMANDS.contains(&name));
+            if self.open
+                || list
+                || command
+                || rest.trim().is_empty()
+                || indentation(rest) < self.indent + 4
+            {
+                return None;
+            }
+            let mut len = 0;
+            let mut offset = 0;
+            for line in rest.split_inclusive('\n') {
+                if !line.trim().is_empty() {
+                    if indentation(line) < self.indent + 4 {
+                        break;
+                    }
+                    len = offset + line.len();
+                }
+                offset += line.len();
+            }
+            // a whitespace-only line is not code by itself
+            if len == 0 {
+                return None;
+            }
+            let code = dedent(&rest[..len]);
+            (
+                len,
+                Block::Code {
+                    language: None,
+                    code,
+                },
+            )
+        };
+        let len = rest[..len].chars().count();
+        self.toks.take(len).consume();
+        Some(block)
+    }
+
+    /// Parses the attribute list and name following `@param`.
+    fn parse_param(&mut self) -> Result<Param, TransformError> {
+        let mut param = Param {
+            name: take_word(&mut self.toks),
+            ..Param::default()
+        };
+        if param.name.is_empty() {
+            if self.toks.peek() != Some('[') {
+                return Err(TransformError::MissingParamName {
+                    location: self.location(),
+                });
+            }
+            self.toks.next();
+            let attributes = self
+                .toks
+                .take_while(|&c| c != ']' && c != '\n')
+                .collect::<String>();
+            if self.toks.peek() != Some(']') {
+                return Err(TransformError::MalformedParamAttributes {
+                    location: self.location(),
+                });
+            }
+            self.toks.next();
+            param.attributes = attributes
+                .split(',')
+                .map(|attr| attr.trim().to_owned())
+                .collect();
+            skip_blanks(&mut self.toks);
+            param.name = take_word(&mut self.toks);
+            if param.name.is_empty() {
+                return Err(TransformError::MissingParamName {
+                    location: self.location(),
+                });
+            }
+        }
+        skip_blanks(&mut self.toks);
+        Ok(param)
+    }
+
+    /// Returns the current position in the comment.
+    fn location(&self) -> Location {
+        Location::new(self.source, self.toks.offset())
+    }
+
+    /// Returns whether the list marker of length `len` at the current position starts an item,
+    /// where a numbered list can only interrupt a paragraph at 1, like in CommonMark.
+    fn starts_list_item(&mut self, len: usize) -> bool {
+        let number = self.toks.remaining()[..len]
+            .trim_end()
+            .trim_end_matches('.');
+        if !self.open || number.parse::<u64>().is_err() || number == "1" {
+            return true;
+        }
+        let blocks = open_content(&mut self.blocks);
+        matches!(blocks.last(), Some(Block::List { .. }))
+    }
+
+    /// Returns the indentation of the line up to `offset`, unless text precedes it.
+    fn column(&self, offset: usize) -> Option<usize> {
+        let line = self.source[..offset].rsplit('\n').next().unwrap_or("");
+        line.trim().is_empty().then(|| indentation(line))
+    }
+
+    /// Starts a list item, nested below the previous item if its marker at `column` is indented
+    /// further.
+    fn push_list_item(&mut self, ordered: bool, column: Option<usize>) {
+        // list items directly following a note, condition or parameter are part of its content
+        let blocks = if self.open {
+            open_content(&mut self.blocks)
+        } else {
+            &mut self.blocks
+        };
+        let indents = &mut self.list_indents;
+        let depth = match (blocks.last(), column) {
+            (Some(Block::List { .. }), Some(column)) => {
+                let depth = indents.iter().position(|&indent| indent >= column);
+                let depth = depth.unwrap_or(indents.len());
+                indents.truncate(depth);
+                indents.push(column);
+                depth
+            }
+            // an item in the middle of a line continues the innermost list
+            (Some(Block::List { .. }), None) => indents.len().saturating_sub(1),
+            (_, column) => {
+                *indents = vec![column.unwrap_or(0)];
+                0
+            }
+        };
+        let blocks = nested_list(blocks, depth);
+        match blocks.last_mut() {
+            Some(Block::List {
+                ordered: last,
+                items,
+            }) if *last == ordered => items.push(vec![]),
+            _ => blocks.push(Block::List {
+                ordered,
+                items: vec![vec![]],
+            }),
+        }
+        self.open = true;
+    }
+
+    /// Returns whether the line after a blank line is an indented part of a note, a condition,
+    /// a return or entry description, or a list item.
+    fn continues_description(&self) -> bool {
+        let contained = matches!(
+            self.blocks.last(),
+            Some(Block::Note { .. } | Block::Conditions { .. } | Block::Returns(_))
+        );
+        let list = matches!(self.blocks.last(), Some(Block::List { .. }));
+        if !contained && !list && !has_entries(self.blocks.last()) {
+            return false;
+        }
+        // list items continue with lines indented past their marker
+        let indent = if list {
+            self.list_indents.first().copied().unwrap_or(self.indent)
+        } else {
+            self.indent
+        };
+        let line = self.next_line();
+        indentation(line) > indent && command_name(line.trim_start()).is_none()
+    }
+
+    /// Returns the next line that is not blank.
+    fn next_line(&self) -> &'a str {
+        let mut lines = self.toks.remaining().lines();
+        lines.find(|line| !line.trim().is_empty()).unwrap_or("")
+    }
+
+    /// Starts a paragraph after a blank line in the open note, condition, description or list
+    /// item, descending into the nested list the next line is indented below.
+    fn continue_paragraph(&mut self) {
+        let column = indentation(self.next_line());
+        let depth = self
+            .list_indents
+            .iter()
+            .filter(|&&indent| indent < column)
+            .count();
+        nested_list(open_content(&mut self.blocks), depth).push(Block::Paragraph(vec![]));
+    }
+
+    /// Parses inline content up to the end of the line or the next block command.
+    fn parse_inlines(&mut self) -> Result<(), TransformError> {
+        loop {
+            match self.toks.peek() {
+                None => return Ok(()),
+                Some('\n') => {
+                    self.toks.next();
+                    self.line_break = true;
+                    return Ok(());
+                }
+                Some('@' | '\\') if self.block_command().is_some() => return Ok(()),
+                Some('@' | '\\') if self.conditional_command().is_some() => {
+                    self.parse_conditional()?
+                }
+                Some('@' | '\\') if self.format_command().is_some() => self.parse_format()?,
+                Some('@' | '\\') => self.parse_inline_command()?,
+                Some('{') if javadoc_tag(self.toks.remaining()).is_some() => self.parse_javadoc(),
+                Some('<') if self.options.convert_html => self.parse_html(),
+                Some(c) => {
+                    self.toks.next();
+                    let mut text = c.to_string();
+                    // a brace that does not start a javadoc tag is text
+                    let rest = self
+                        .toks
+                        .take_while(|&c| !matches!(c, '\n' | '@' | '\\' | '{' | '<'))
+                        .collect::<String>();
+                    text.push_str(&rest);
+                    if matches!(self.toks.peek(), None | Some('\n'))
+                        || self.block_command().is_some()
+                    {
+                        text.truncate(text.trim_end().len());
+                    }
+                    if !text.is_empty() {
+                        self.push_inline(Inline::Text(text));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the name of the format-specific command at the current position, if any.
+    fn format_command(&self) -> Option<&'static str> {
+        let name = command_name(self.toks.remaining())?;
+        FORMAT_COMMANDS.iter().copied().find(|&cmd| cmd == name)
+    }
+
+    /// Parses a format-specific block, keeping the content of `@htmlonly` as raw HTML and
+    /// dropping that of other formats.
+    fn parse_format(&mut self) -> Result<(), TransformError> {
+        let name = self.format_command().expect("no format command");
+        let start = self.toks.location();
+        let spaced = self
+            .toks
+            .consumed()
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        self.toks.take(name.len() + 1).consume();
+        // `@htmlonly[block]` is not wrapped in a paragraph
+        let block = name == "htmlonly" && self.toks.tokens("[block]".chars());
+        let content = match self.take_until_end(name, start) {
+            Ok(content) => content,
+            Err(err) => return self.recover(err, start, name),
+        };
+        if block {
+            self.push_code(Block::Html(dedent(&content)), self.column(start.offset()));
+        } else if name == "htmlonly" {
+            self.push_inline(Inline::Html(content));
+        } else if spaced {
+            skip_blanks(&mut self.toks);
+        }
+        Ok(())
+    }
+
+    /// Returns the name of the conditional command at the current position, if any.
+    fn conditional_command(&self) -> Option<&'static str> {
+        let name = command_name(self.toks.remaining())?;
+        CONDITIONAL_COMMANDS
+            .iter()
+            .copied()
+            .find(|&cmd| cmd == name)
+    }
+
+    /// Returns whether text at the current position is included.
+    fn active(&self) -> bool {
+        self.conditions
+            .last()
+            .is_none_or(|condition| condition.active)
+    }
+
+    /// Parses a conditional command, skipping any text it excludes.
+    fn parse_conditional(&mut self) -> Result<(), TransformError> {
+        let mut spaced = self.conditional()?;
+        while !self.active() {
+            let rest = self.toks.remaining();
+            let next = rest
+                .match_indices(['@', '\\'])
+                .find(|&(i, _)| {
+                    command_name(&rest[i..])
+                        .is_some_and(|name| CONDITIONAL_COMMANDS.contains(&name))
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            self.toks.take(rest[..next].chars().count()).consume();
+            if self.toks.peek().is_none() {
+                break;
+            }
+            spaced = self.conditional()?;
+        }
+        // avoid doubled spaces where a command was surrounded by text
+        if spaced {
+            skip_blanks(&mut self.toks);
+        }
+        Ok(())
+    }
+
+    /// Parses a single conditional command, returning whether whitespace preceded it.
+    fn conditional(&mut self) -> Result<bool, TransformError> {
+        let name = self.conditional_command().expect("no conditional command");
+        let start = self.toks.offset();
+        let location = self.toks.location();
+        let spaced = self
+            .toks
+            .consumed()
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        self.toks.take(name.len() + 1).consume();
+        let parent = self.active();
+        match name {
+            "if" | "ifnot" | "cond" => {
+                let value = match self.label_expression(name == "cond") {
+                    Ok(value) => value,
+                    Err(err) => return self.recover_conditional(err, location, name, parent),
+                };
+                let value = value != (name == "ifnot");
+                let command = if name == "cond" { "cond" } else { "if" };
+                // an unclosed condition is kept as text rather than hiding the rest
+                if self.lenient && !self.is_closed(command) {
+                    let err = TransformError::UnterminatedBlock {
+                        command: command.to_owned(),
+                        location: Location::new(self.source, start),
+                    };
+                    return self.recover_conditional(err, location, name, parent);
+                }
+                self.conditions.push(Condition {
+                    command,
+                    start,
+                    active: parent && value,
+                    parent,
+                    taken: value,
+                });
+            }
+            "internal" => {
+                // internal documentation extends to the end of the comment by default
+                self.internal = true;
+                self.conditions.push(Condition {
+                    command: "internal",
+                    start,
+                    active: parent && self.options.keep_internal,
+                    parent,
+                    taken: true,
+                });
+            }
+            "else" | "elseif" => {
+                let value = match name {
+                    "elseif" => match self.label_expression(false) {
+                        Ok(value) => value,
+                        Err(err) => return self.recover_conditional(err, location, name, parent),
+                    },
+                    _ => true,
+                };
+                if let Some(condition) = self.conditions.last_mut()
+                    && condition.command == "if"
+                {
+                    let value = value && !condition.taken;
+                    condition.active = condition.parent && value;
+                    condition.taken |= value;
+                }
+            }
+            _ => {
+                let command = match name {
+                    "endcond" => "cond",
+                    "endinternal" => "internal",
+                    _ => "if",
+                };
+                if self
+                    .conditions
+                    .last()
+                    .is_some_and(|condition| condition.command == command)
+                {
+                    self.conditions.pop();
+                }
+            }
+        }
+        Ok(spaced)
+    }
+
+    /// Parses and evaluates the label expression following `@if` or `@cond`, where a `@cond`
+    /// without a label excludes its text.
+    fn label_expression(&mut self, optional: bool) -> Result<bool, TransformError> {
+        skip_blanks(&mut self.toks);
+        let location = self.location();
+        let expr = if self.toks.peek() == Some('(') {
+            // up to the matching parenthesis
+            let mut depth = 0;
+            let mut expr = self
+                .toks
+                .take_while(|&c| {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    depth > 0 && c != '\n'
+                })
+                .collect::<String>();
+            if self.toks.token(')') {
+                expr.push(')');
+            }
+            expr
+        } else {
+            take_word(&mut self.toks)
+        };
+        if optional && expr.is_empty() {
+            return Ok(false);
+        }
+        evaluate(&expr, &self.options.enabled_sections)
+            .ok_or(TransformError::MalformedCondition { location })
+    }
+
+    /// Returns whether the remaining text contains the end command of a condition opened by
+    /// `command`, following the nesting of the conditions in between.
+    fn is_closed(&self, command: &'static str) -> bool {
+        let rest = self.toks.remaining();
+        let mut open = vec![command];
+        let names = rest
+            .match_indices(['@', '\\'])
+            .filter_map(|(i, _)| command_name(&rest[i..]))
+            .filter(|name| CONDITIONAL_COMMANDS.contains(name));
+        for name in names {
+            match name {
+                "if" | "ifnot" => open.push("if"),
+                "cond" | "internal" => open.push(name),
+                "endif" | "endcond" | "endinternal" => {
+                    if open.last() == Some(&&name[3..]) {
+                        open.pop();
+                    }
+                    if open.is_empty() {
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Keeps a conditional command with a malformed expression or without an end command as text
+    /// in lenient mode, without opening or changing a condition.
+    fn recover_conditional(
+        &mut self,
+        err: TransformError,
+        start: StrTokensLocation,
+        name: &str,
+        active: bool,
+    ) -> Result<bool, TransformError> {
+        if active {
+            self.recover(err, start, name)?;
+        } else if self.lenient {
+            // excluded text is dropped either way
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                error: err,
+            });
+        } else {
+            return Err(err);
+        }
+        Ok(false)
+    }
+
+    /// Reports conditional sections left open at the end of the comment.
+    fn finish_conditions(&mut self) -> Result<(), TransformError> {
+        let conditions = std::mem::take(&mut self.conditions);
+        for condition in conditions.into_iter().filter(|c| c.command != "internal") {
+            let error = TransformError::UnterminatedBlock {
+                command: condition.command.to_owned(),
+                location: Location::new(self.source, condition.start),
+            };
+            if !self.lenient {
+                return Err(error);
+            }
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                error,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts the HTML tag at the current position into the equivalent markdown, keeping
+    /// balanced tags that have none and leaving other tags as text, which renderers escape.
+    fn parse_html(&mut self) {
+        let rest = self.toks.remaining();
+        let Some(tag) = tags::tag(rest) else {
+            self.toks.next();
+            self.push_inline(Inline::Text("<".to_owned()));
+            return;
+        };
+        let raw = rest[..tag.len].to_owned();
+        let after = &rest[tag.len..];
+        let end = find_end(after, &tag.name).filter(|_| !tag.end);
+        if INLINE_TAGS.contains(&tag.name.as_str())
+            && let Some(end) = end
+            && !after[..end].contains(['<', '@', '\\'])
+        {
+            let content = &after[..end];
+            let text = content.split_whitespace().collect::<Vec<_>>().join(" ");
+            let inline = match tag.name.as_str() {
+                "b" | "strong" => Inline::Strong(text),
+                "i" | "em" => Inline::Emphasis(text),
+                "tt" | "code" => Inline::Code(decode_entities(content.trim())),
+                _ => match (
+                    tag.attribute("href"),
+                    tag.attribute("name").or(tag.attribute("id")),
+                ) {
+                    (Some(href), _) => Inline::Ref(Reference {
+                        target: href.to_owned(),
+                        text: link_text(&text),
+                    }),
+                    (None, Some(name)) => Inline::Anchor(name.to_owned()),
+                    (None, None) => Inline::Text(text),
+                },
+            };
+            let len = raw.len() + end + "</>".len() + tag.name.len();
+            let len = rest[..len].chars().count();
+            self.toks.take(len).consume();
+            // empty spans would render as stray markup, while empty anchors are targets
+            if !content.trim().is_empty() || tag.name == "a" {
+                self.push_inline(inline);
+            }
+            return;
+        }
+        if tag.name == "pre"
+            && let Some(end) = end
+        {
+            let code = dedent(&decode_entities(&after[..end]));
+            let len = raw.len() + end + "</pre>".len();
+            let len = rest[..len].chars().count();
+            self.toks.take(len).consume();
+            self.blocks.push(Block::Code {
+                language: None,
+                code,
+            });
+            self.open = false;
+            return;
+        }
+        let kept = (tag.is_kept() || INLINE_TAGS.contains(&tag.name.as_str()))
+            && (tag.is_void() || end.is_some());
+        let closes = tag.end && self.html_open.contains(&tag.name);
+        self.toks.take(raw.chars().count()).consume();
+        match (tag.name.as_str(), tag.end) {
+            ("br", false) => self.push_inline(Inline::LineBreak),
+            ("p", false) => {
+                if self.open && !self.target().is_empty() {
+                    self.new_paragraph();
+                }
+            }
+            ("p" | "li", true) => {}
+            ("ul" | "ol", false) => self.html_lists.push(tag.name == "ol"),
+            ("ul" | "ol", true) => {
+                self.html_lists.pop();
+                if self.html_lists.is_empty() {
+                    self.open = false;
+                }
+            }
+            ("li", false) => {
+                let ordered = self.html_lists.last().copied().unwrap_or(false);
+                let column = self.html_lists.len().saturating_sub(1);
+                self.push_list_item(ordered, Some(column));
+                skip_blanks(&mut self.toks);
+            }
+            _ if kept => {
+                if !tag.is_void() {
+                    self.html_open.push(tag.name.clone());
+                }
+                self.push_inline(Inline::Html(raw));
+            }
+            _ if closes => {
+                let i = self.html_open.iter().rposition(|name| *name == tag.name);
+                self.html_open.remove(i.expect("no open element"));
+                self.push_inline(Inline::Html(raw));
+            }
+            _ => self.push_inline(Inline::Text(raw)),
+        }
+    }
+
+    /// Parses a Javadoc inline tag such as `{@link Foo}` or `{@code x}`.
+    fn parse_javadoc(&mut self) {
+        let tag = javadoc_tag(self.toks.remaining()).expect("no javadoc tag");
+        self.toks.take(tag.len() + 2).consume();
+        skip_blanks(&mut self.toks);
+        // up to the matching brace
+        let mut depth = 1;
+        let content = self
+            .toks
+            .take_while(|&c| {
+                depth += match c {
+                    '{' => 1,
+                    '}' => -1,
+                    _ => 0,
+                };
+                depth > 0 && c != '\n'
+            })
+            .collect::<String>();
+        self.toks.token('}');
+        let inline = match tag {
+            "code" | "literal" => Inline::Code(content.trim().to_owned()),
+            _ => {
+                let (target, text) = content
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((content.trim(), ""));
+                Inline::Ref(Reference {
+                    target: javadoc_target(target),
+                    text: link_text(text),
+                })
+            }
+        };
+        self.push_inline(inline);
+    }
+
+    fn parse_inline_command(&mut self) -> Result<(), TransformError> {
+        let start = self.toks.location();
+        let location = self.location();
+        let word_start = self
+            .toks
+            .consumed()
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        let spaced = self
+            .toks
+            .consumed()
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        let tok = self.toks.next().unwrap_or('@');
+        let inline = match self.toks.peek() {
+            Some('{' | '}') => {
+                // member groups are not implemented
+                self.toks.next();
+                return Ok(());
+            }
+            Some(c) if ESCAPES.contains(&c) => {
+                self.toks.next();
+                Inline::Text(c.to_string())
+            }
+            _ => {
+                let tag = self
+                    .toks
+                    .take_while(|c| c.is_ascii_alphanumeric())
+                    .collect::<String>();
+                // words end at converted HTML tags, e.g. in `<b>@c x</b>`
+                let html = self.options.convert_html;
+                let mut word = || {
+                    skip_blanks(&mut self.toks);
+                    self.toks
+                        .take_while(|&c| !(SEPS.contains(&c) || html && c == '<'))
+                        .collect::<String>()
+                };
+                match tag.as_str() {
+                    "c" | "p" => Inline::Code(word()),
+                    "a" | "e" | "em" => Inline::Emphasis(word()),
+                    "b" => Inline::Strong(word()),
+                    "ref" => {
+                        let word = word();
+                        let target = ref_target(&word);
+                        if target.len() < word.len() {
+                            let reference = Reference {
+                                target: target.to_owned(),
+                                text: None,
+                            };
+                            self.push_inline(Inline::Ref(reference));
+                            Inline::Text(word[target.len()..].to_owned())
+                        } else {
+                            Inline::Ref(Reference {
+                                target: word,
+                                text: self.quoted_text(),
+                            })
+                        }
+                    }
+                    "link" => {
+                        let target = word();
+                        let text = match self.take_until_end("link", start) {
+                            Ok(text) => text,
+                            Err(err) => return self.recover(err, start, "link"),
+                        };
+                        Inline::Ref(Reference {
+                            target,
+                            text: link_text(&text),
+                        })
+                    }
+                    "anchor" => {
+                        let anchor = Inline::Anchor(word());
+                        // avoid doubled spaces where the anchor was surrounded by text
+                        if spaced {
+                            skip_blanks(&mut self.toks);
+                        }
+                        anchor
+                    }
+                    "private" => {
+                        self.private = true;
+                        if spaced {
+                            skip_blanks(&mut self.toks);
+                        }
+                        return Ok(());
+                    }
+                    _ => {
+                        if self.lenient && word_start && !tag.is_empty() {
+                            self.diagnostics.push(Diagnostic {
+                                severity: Severity::Warning,
+                                error: TransformError::UnknownCommand {
+                                    command: tag.clone(),
+                                    location,
+                                },
+                            });
+                        }
+                        Inline::Text(format!("{tok}{tag}"))
+                    }
+                }
+            }
+        };
+        self.push_inline(inline);
+        Ok(())
+    }
+
+    /// Takes a double-quoted argument such as the link text of `@ref`, if one follows.
+    fn quoted_text(&mut self) -> Option<String> {
+        let start = self.toks.location();
+        skip_blanks(&mut self.toks);
+        if self.toks.token('"') {
+            let text = self
+                .toks
+                .take_while(|&c| c != '"' && c != '\n')
+                .collect::<String>();
+            if self.toks.token('"') {
+                return link_text(&text);
+            }
+        }
+        self.toks.set_location(start);
+        None
+    }
+
+    /// Appends an inline to the open block, starting a paragraph if needed.
+    fn push_inline(&mut self, inline: Inline) {
+        let blank = matches!(&inline, Inline::Text(text) if text.trim().is_empty());
+        if !blank && self.conditions.iter().all(|c| c.command != "internal") {
+            self.public = true;
+        }
+        let line_break = std::mem::take(&mut self.line_break);
+        let target = self.target();
+        // a hard line break already ends the line
+        if line_break && !target.is_empty() && target.last() != Some(&Inline::LineBreak) {
+            target.push(Inline::SoftBreak);
+        }
+        match (target.last_mut(), inline) {
+            (Some(Inline::Text(last)), Inline::Text(text)) => last.push_str(&text),
+            (_, inline) => target.push(inline),
+        }
+    }
+
+    /// Returns the inline content text is currently appended to.
+    fn target(&mut self) -> &mut Vec<Inline> {
+        if !self.open {
+            self.blocks.push(Block::Paragraph(vec![]));
+            self.open = true;
+        }
+        let content = match self.blocks.last_mut() {
+            Some(Block::Paragraph(inlines)) => return inlines,
+            Some(Block::Section { title, .. }) => return title,
+            Some(Block::TitledParagraph { title, content }) => {
+                if self.title {
+                    return title;
+                }
+                content
+            }
+            Some(Block::List { items, .. } | Block::Conditions { items, .. }) => {
+                items.last_mut().expect("list without items")
+            }
+            Some(Block::Note { content, .. }) => content,
+            Some(
+                block @ (Block::Params(_)
+                | Block::TypeParams(_)
+                | Block::ReturnValues(_)
+                | Block::Exceptions(_)
+                | Block::SeeAlso(_)),
+            ) => entry_description(block).expect("empty list of entries"),
+            Some(Block::Returns(content)) => content,
+            Some(Block::Code { .. } | Block::Html(_)) | None => unreachable!("no open block"),
+        };
+        open_paragraph(content)
+    }
+}
@@ -0,0 +1,260 @@
+//! Rendering of a [`DocComment`] as HTML.
+
+use super::{
+    Renderer, anchors, code_language, grouped, heading_level, note_label, section_level,
+    section_title,
+};
+use crate::ast::{Block, DocComment, Inline, Param, Reference};
+use crate::transformer::{Options, ReturnValueStyle, TitleStyle};
+
+/// Renders an HTML fragment, e.g. for previews.
+///
+/// Text is escaped, so HTML markup within comments is only preserved where it was parsed as such,
+/// e.g. by `@htmlonly` or the elements [`Options::convert_html`] keeps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Html;
+
+impl Renderer for Html {
+    fn render(&self, doc: &DocComment, options: &Options) -> String {
+        let mut html = Writer {
+            out: String::new(),
+            options,
+            sections: vec![],
+            anchors: anchors(doc),
+        };
+        html.blocks(&doc.blocks);
+        html.out
+    }
+}
+
+/// Escapes HTML special characters.
+fn escape(str: &str) -> String {
+    str.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+struct Writer<'a> {
+    out: String,
+    options: &'a Options,
+    /// Section titles emitted so far.
+    sections: Vec<&'a str>,
+    /// Anchors of `@section` headings and their titles.
+    anchors: Vec<(&'a str, String)>,
+}
+
+impl Writer<'_> {
+    fn blocks(&mut self, blocks: &[Block]) {
+        for block in grouped(blocks, self.options) {
+            let title =
+                section_title(block, self.options).filter(|title| !self.sections.contains(title));
+            if let Some(title) = title {
+                self.sections.push(title);
+                let level = heading_level(1, self.options);
+                self.out
+                    .push_str(&format!("<h{level}>{}</h{level}>\n", escape(title)));
+            }
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph(inlines) => {
+                self.out.push_str("<p>");
+                self.inlines(inlines);
+                self.out.push_str("</p>\n");
+            }
+            Block::TitledParagraph { title, content } => {
+                let (start, end) = match self.options.par_title_style {
+                    TitleStyle::Heading => {
+                        let level = heading_level(1, self.options);
+                        (format!("<h{level}>"), format!("</h{level}>\n"))
+                    }
+                    TitleStyle::Bold => ("<p><strong>".to_owned(), "</strong></p>\n".to_owned()),
+                };
+                self.out.push_str(&start);
+                self.inlines(title);
+                self.out.push_str(&end);
+                self.blocks(content);
+            }
+            Block::Section {
+                level,
+                anchor,
+                title,
+            } => {
+                let level = section_level(*level, self.options);
+                match anchor.as_str() {
+                    "" => self.out.push_str(&format!("<h{level}>")),
+                    anchor => self
+                        .out
+                        .push_str(&format!("<h{level} id=\"{}\">", escape(anchor))),
+                }
+                self.inlines(title);
+                self.out.push_str(&format!("</h{level}>\n"));
+            }
+            Block::List { items, .. } | Block::Conditions { items, .. } => {
+                let tag = match block {
+                    Block::List { ordered: true, .. } => "ol",
+                    _ => "ul",
+                };
+                self.out.push_str(&format!("<{tag}>\n"));
+                for item in items {
+                    self.out.push_str("<li>");
+                    self.blocks(item);
+                    self.out.push_str("</li>\n");
+                }
+                self.out.push_str(&format!("</{tag}>\n"));
+            }
+            Block::Code { language, code } => {
+                let language = code_language(language.as_deref(), self.options);
+                self.out.push_str(&format!(
+                    "<pre><code class=\"language-{}\">",
+                    escape(language)
+                ));
+                self.out.push_str(&escape(code));
+                self.out.push_str("</code></pre>\n");
+            }
+            Block::Html(html) => {
+                self.out.push_str(html);
+                self.out.push('\n');
+            }
+            Block::Note { kind, content } => {
+                let (start, end) = if kind.is_warning() {
+                    ("<div class=\"warning\">\n", "</div>\n")
+                } else {
+                    ("<blockquote>\n", "</blockquote>\n")
+                };
+                self.out.push_str(start);
+                if let Some(label) = note_label(*kind, self.options) {
+                    self.out
+                        .push_str(&format!("<strong>{}</strong>\n", escape(label)));
+                }
+                self.blocks(content);
+                self.out.push_str(end);
+            }
+            Block::Params(params) | Block::TypeParams(params) => {
+                self.out.push_str("<ul>\n");
+                for param in params {
+                    self.param(param);
+                }
+                self.out.push_str("</ul>\n");
+            }
+            Block::Returns(content) => self.blocks(content),
+            Block::ReturnValues(values) => {
+                let table = self.options.return_value_style == ReturnValueStyle::Table;
+                let (start, value_tags, description_tags, end) = if table {
+                    (
+                        "<table>\n<tr><th>Value</th><th>Description</th></tr>\n",
+                        ("<tr><td>", "</td>"),
+                        ("<td>\n", "</td></tr>\n"),
+                        "</table>\n",
+                    )
+                } else {
+                    (
+                        "<dl>\n",
+                        ("<dt>", "</dt>\n"),
+                        ("<dd>\n", "</dd>\n"),
+                        "</dl>\n",
+                    )
+                };
+                self.out.push_str(start);
+                for value in values {
+                    self.out.push_str(value_tags.0);
+                    self.out
+                        .push_str(&format!("<code>{}</code>", escape(&value.value)));
+                    self.out.push_str(value_tags.1);
+                    self.out.push_str(description_tags.0);
+                    self.blocks(&value.description);
+                    self.out.push_str(description_tags.1);
+                }
+                self.out.push_str(end);
+            }
+            Block::Exceptions(exceptions) => {
+                self.out.push_str("<ul>\n");
+                for exception in exceptions {
+                    self.out
+                        .push_str(&format!("<li><code>{}</code>", escape(&exception.name)));
+                    if !exception.description.is_empty() {
+                        self.out.push_str(" -\n");
+                        self.blocks(&exception.description);
+                    }
+                    self.out.push_str("</li>\n");
+                }
+                self.out.push_str("</ul>\n");
+            }
+            Block::SeeAlso(entries) => {
+                self.out.push_str("<ul>\n");
+                for entry in entries {
+                    self.out.push_str("<li>");
+                    if let Some(reference) = &entry.reference {
+                        self.reference(reference);
+                    }
+                    if !entry.description.is_empty() {
+                        self.out.push('\n');
+                        self.blocks(&entry.description);
+                    }
+                    self.out.push_str("</li>\n");
+                }
+                self.out.push_str("</ul>\n");
+            }
+        }
+    }
+
+    fn param(&mut self, param: &Param) {
+        self.out
+            .push_str(&format!("<li><code>{}</code>", escape(&param.name)));
+        if !param.attributes.is_empty() {
+            let attributes = escape(&param.attributes.join(", "));
+            self.out.push_str(&format!(" [{attributes}]"));
+        }
+        if !param.description.is_empty() {
+            self.out.push_str(" -\n");
+            self.blocks(&param.description);
+        }
+        self.out.push_str("</li>\n");
+    }
+
+    fn reference(&mut self, reference: &Reference) {
+        let target = escape(&reference.target);
+        let text = reference.text.as_deref().map(escape);
+        let anchor = self
+            .anchors
+            .iter()
+            .find(|(anchor, _)| *anchor == reference.target);
+        if let Some((_, title)) = anchor {
+            let text = text.unwrap_or_else(|| escape(title));
+            self.out
+                .push_str(&format!("<a href=\"#{target}\">{text}</a>"));
+        } else if reference.target.contains("://") {
+            let text = text.unwrap_or_else(|| target.clone());
+            self.out
+                .push_str(&format!("<a href=\"{target}\">{text}</a>"));
+        } else if let Some(text) = text {
+            self.out.push_str(&text);
+        } else {
+            self.out.push_str(&format!("<code>{target}</code>"));
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => self.out.push_str(&escape(text)),
+                Inline::Html(html) => self.out.push_str(html),
+                Inline::Code(code) => self.out.push_str(&format!("<code>{}</code>", escape(code))),
+                Inline::Emphasis(word) => self.out.push_str(&format!("<em>{}</em>", escape(word))),
+                Inline::Strong(word) => self
+                    .out
+                    .push_str(&format!("<strong>{}</strong>", escape(word))),
+                Inline::Ref(reference) => self.reference(reference),
+                Inline::Anchor(name) => self
+                    .out
+                    .push_str(&format!("<a id=\"{}\"></a>", escape(name))),
+                Inline::SoftBreak => self.out.push('\n'),
+                Inline::LineBreak => self.out.push_str("<br>\n"),
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Rendering of a [`DocComment`] as markdown.
+
+use super::{
+    Renderer, anchors, code_language, grouped, heading_level, indent, note_label, section_level,
+    section_title, text_tags,
+};
+use crate::ast::{Block, DocComment, Inline, Param, Reference};
+use crate::transformer::{Options, ReturnValueStyle, TitleStyle};
+
+/// Renders Rustdoc markdown, with intra-doc links for references and warning blocks for
+/// `@warning`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rustdoc;
+
+impl Renderer for Rustdoc {
+    fn render(&self, doc: &DocComment, options: &Options) -> String {
+        let mut markdown = Markdown::new(doc, options, true);
+        markdown.blocks(&doc.blocks);
+        markdown.out
+    }
+}
+
+/// Renders plain CommonMark, with references as code spans and warnings as quotes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CommonMark;
+
+impl Renderer for CommonMark {
+    fn render(&self, doc: &DocComment, options: &Options) -> String {
+        let mut markdown = Markdown::new(doc, options, false);
+        markdown.blocks(&doc.blocks);
+        markdown.out
+    }
+}
+
+/// Wraps code in a span delimited by more backticks than any run within it.
+fn code_span(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let ticks = "`".repeat(longest + 1);
+    // a space keeps a backtick at either end apart from the delimiter
+    let pad = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{ticks}{pad}{code}{pad}{ticks}")
+}
+
+struct Markdown<'a> {
+    out: String,
+    options: &'a Options,
+    /// Whether to use rustdoc extensions, i.e. intra-doc links and warning blocks.
+    rustdoc: bool,
+    /// Section titles emitted so far.
+    sections: Vec<&'a str>,
+    /// Anchors of `@section` headings and their titles.
+    anchors: Vec<(&'a str, String)>,
+}
+
+impl<'a> Markdown<'a> {
+    fn new(doc: &'a DocComment, options: &'a Options, rustdoc: bool) -> Self {
+        Markdown {
+            out: String::new(),
+            options,
+            rustdoc,
+            sections: vec![],
+            anchors: anchors(doc),
+        }
+    }
+
+    fn heading(&mut self, level: u8) {
+        let level = heading_level(level, self.options);
+        self.hashes(level);
+    }
+
+    fn hashes(&mut self, level: u8) {
+        self.out.push_str(&"#".repeat(usize::from(level)));
+        self.out.push(' ');
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        let blocks = grouped(blocks, self.options);
+        for (i, &block) in blocks.iter().enumerate() {
+            let title =
+                section_title(block, self.options).filter(|title| !self.sections.contains(title));
+            if i > 0 {
+                // lists and quotes may directly follow a line of text, but consecutive quotes
+                // would merge into one, and warning blocks must be surrounded by blank lines
+                let quotes = matches!(
+                    (blocks[i - 1], block),
+                    (Block::Note { .. }, Block::Note { .. })
+                ) || self.is_warning_block(blocks[i - 1])
+                    || self.is_warning_block(block);
+                let list = self.options.return_value_style == ReturnValueStyle::List;
+                let tight = title.is_none()
+                    && !quotes
+                    && match block {
+                        Block::List { .. }
+                        | Block::Conditions { .. }
+                        | Block::Note { .. }
+                        | Block::Params(_)
+                        | Block::TypeParams(_)
+                        | Block::Exceptions(_)
+                        | Block::SeeAlso(_) => true,
+                        Block::ReturnValues(_) => list,
+                        _ => false,
+                    };
+                self.out.push_str(if tight { "\n" } else { "\n\n" });
+            }
+            if let Some(title) = title {
+                self.sections.push(title);
+                self.heading(1);
+                self.out.push_str(title);
+                self.out.push_str("\n\n");
+            }
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph(inlines) => self.inlines(inlines),
+            Block::TitledParagraph { title, content } => {
+                match self.options.par_title_style {
+                    TitleStyle::Heading => {
+                        self.heading(1);
+                        self.inlines(title);
+                    }
+                    TitleStyle::Bold => {
+                        self.out.push_str("**");
+                        self.inlines(title);
+                        self.out.push_str("**");
+                    }
+                }
+                if !content.is_empty() {
+                    self.out.push_str("\n\n");
+                    self.blocks(content);
+                }
+            }
+            Block::Section {
+                level,
+                anchor,
+                title,
+            } => {
+                self.hashes(section_level(*level, self.options));
+                if !anchor.is_empty() {
+                    self.out.push_str(&format!("<a id=\"{anchor}\"></a>"));
+                }
+                self.inlines(title);
+            }
+            Block::List { ordered, items } => self.list(items, *ordered),
+            Block::Conditions { items, .. } => self.list(items, false),
+            Block::Code { language, code } => {
+                // the fence must be longer than any run of backticks in the code
+                let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let fence = "`".repeat(longest.max(2) + 1);
+                let language = code_language(language.as_deref(), self.options);
+                self.out
+                    .push_str(&format!("{fence}{language}\n{code}\n{fence}"));
+            }
+            Block::Html(html) => self.out.push_str(html),
+            Block::Note { kind, content } if self.is_warning_block(block) => {
+                self.out.push_str("<div class=\"warning\">\n\n");
+                if let Some(label) = note_label(*kind, self.options) {
+                    self.out.push_str(&format!("**{label}** "));
+                }
+                self.blocks(content);
+                self.out.push_str("\n\n</div>");
+            }
+            Block::Note { kind, content } => {
+                self.out.push_str("> ");
+                if let Some(label) = note_label(*kind, self.options) {
+                    self.out.push_str(&format!("**{label}** "));
+                }
+                // quote every line of the note, not just the first
+                let start = self.out.len();
+                self.blocks(content);
+                let content = self.out.split_off(start);
+                self.out.push_str(&indent(&content, "> "));
+            }
+            Block::Params(params) | Block::TypeParams(params) => {
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push('\n');
+                    }
+                    self.param(param);
+                }
+            }
+            Block::Returns(content) => self.blocks(content),
+            Block::ReturnValues(values) => match self.options.return_value_style {
+                ReturnValueStyle::Table => {
+                    self.out.push_str("| Value | Description |\n| --- | --- |");
+                    for value in values {
+                        self.out.push_str("\n| ");
+                        self.code(&value.value, self.options.link_return_values);
+                        // table cells cannot span lines
+                        let start = self.out.len();
+                        self.blocks(&value.description);
+                        let description = self.out.split_off(start);
+                        let description = description.replace('|', "\\|").replace('\n', " ");
+                        self.out.push_str(&format!(" | {description} |"));
+                    }
+                }
+                ReturnValueStyle::List => {
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            self.out.push('\n');
+                        }
+                        self.out.push(self.options.param_bullet);
+                        self.out.push(' ');
+                        self.code(&value.value, self.options.link_return_values);
+                        self.description(&value.description);
+                    }
+                }
+            },
+            Block::Exceptions(exceptions) => {
+                for (i, exception) in exceptions.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push('\n');
+                    }
+                    self.out.push(self.options.param_bullet);
+                    self.out.push(' ');
+                    self.code(&exception.name, self.options.link_exceptions);
+                    self.description(&exception.description);
+                }
+            }
+            Block::SeeAlso(entries) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push('\n');
+                    }
+                    self.out.push(self.options.bullet);
+                    self.out.push(' ');
+                    if let Some(reference) = &entry.reference {
+                        self.reference(reference);
+                        if !entry.description.is_empty() {
+                            self.out.push(' ');
+                        }
+                    }
+                    self.nested(&entry.description);
+                }
+            }
+        }
+    }
+
+    fn param(&mut self, param: &Param) {
+        let attributes = if param.attributes.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}] ", param.attributes.join(", "))
+        };
+        let bullet = self.options.param_bullet;
+        self.out
+            .push_str(&format!("{bullet} `{}`{attributes}", param.name));
+        self.description(&param.description);
+    }
+
+    /// Renders the description following the name of a list entry.
+    fn description(&mut self, description: &[Block]) {
+        self.out.push_str(" -");
+        if !description.is_empty() {
+            self.out.push(' ');
+            self.nested(description);
+        }
+    }
+
+    /// Renders list items, numbering them from 1 if `ordered`.
+    fn list(&mut self, items: &[Vec<Block>], ordered: bool) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+            let marker = if ordered {
+                format!("{}.", i + 1)
+            } else {
+                self.options.bullet.to_string()
+            };
+            self.out.push_str(&marker);
+            self.out.push(' ');
+            // nested lists must be aligned with the content of the item
+            self.indented(item, &" ".repeat(marker.len() + 1));
+        }
+    }
+
+    /// Renders the content of a list item, indenting its continuation lines.
+    fn nested(&mut self, blocks: &[Block]) {
+        self.indented(blocks, "  ");
+    }
+
+    /// Renders blocks, prefixing all lines but the first.
+    fn indented(&mut self, blocks: &[Block], prefix: &str) {
+        let start = self.out.len();
+        self.blocks(blocks);
+        let content = self.out.split_off(start);
+        self.out.push_str(&indent(&content, prefix));
+    }
+
+    /// Returns whether a block is rendered as a rustdoc warning block.
+    fn is_warning_block(&self, block: &Block) -> bool {
+        matches!(block, Block::Note { kind, .. } if self.rustdoc && kind.is_warning())
+    }
+
+    /// Formats a name as code, or as a link to the item of that name.
+    fn code(&mut self, name: &str, link: bool) {
+        if link {
+            self.reference(&Reference {
+                target: name.to_owned(),
+                text: None,
+            });
+        } else {
+            self.out.push_str(&format!("`{name}`"));
+        }
+    }
+
+    /// Formats a reference as a link.
+    fn reference(&mut self, reference: &Reference) {
+        let str = &reference.target;
+        let text = reference.text.as_deref();
+        if let Some((_, title)) = self.anchors.iter().find(|(anchor, _)| anchor == str) {
+            let text = text.unwrap_or(title);
+            self.out.push_str(&format!("[{text}](#{str})"));
+        } else if str.contains("://") {
+            let text = text.unwrap_or(str);
+            self.out.push_str(&format!("[{text}]({str})"));
+        } else if let Some(text) = text {
+            // intra-doc links only resolve in rustdoc
+            if self.rustdoc {
+                self.out.push_str(&format!("[{text}]({str})"));
+            } else {
+                self.out.push_str(text);
+            }
+        } else if self.rustdoc {
+            self.out.push_str(&format!("[`{str}`]"));
+        } else {
+            self.out.push_str(&format!("`{str}`"));
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => self.out.push_str(&text_tags(text, self.options)),
+                Inline::Html(html) => self.out.push_str(html),
+                Inline::Code(code) => self.out.push_str(&code_span(code)),
+                Inline::Emphasis(word) => self.out.push_str(&format!("_{word}_")),
+                Inline::Strong(word) => self.out.push_str(&format!("**{word}**")),
+                Inline::Ref(reference) => self.reference(reference),
+                Inline::Anchor(name) => self.out.push_str(&format!("<a id=\"{name}\"></a>")),
+                Inline::SoftBreak => self.out.push('\n'),
+                Inline::LineBreak => self.out.push_str("\\\n"),
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Output backends for a parsed [`DocComment`].
+
+mod html;
+mod markdown;
+mod text;
+
+use crate::ast::{Block, ConditionKind, DocComment, Inline, NoteKind};
+use crate::transformer::Options;
+use std::borrow::Cow;
+
+pub use html::Html;
+pub use markdown::{CommonMark, Rustdoc};
+pub use text::PlainText;
+
+/// Renders a parsed comment into an output format.
+pub trait Renderer {
+    /// Renders the comment.
+    fn render(&self, doc: &DocComment, options: &Options) -> String;
+}
+
+/// Returns the title of the section a block starts.
+fn section_title<'a>(block: &Block, options: &'a Options) -> Option<&'a str> {
+    match block {
+        Block::Params(_) => Some(&options.arguments_title),
+        Block::TypeParams(_) => Some(&options.type_parameters_title),
+        Block::Returns(_) | Block::ReturnValues(_) => Some(&options.returns_title),
+        Block::Exceptions(_) => Some(&options.exceptions_title),
+        Block::Conditions { kind, .. } => Some(match kind {
+            ConditionKind::Pre => &options.precondition_title,
+            ConditionKind::Post => &options.postcondition_title,
+            ConditionKind::Invariant => &options.invariant_title,
+        }),
+        Block::SeeAlso(_) => Some(&options.see_also_title),
+        _ => None,
+    }
+}
+
+/// Orders blocks so that all blocks of a section follow its first one, e.g. a `@post` after a
+/// `@pre` is listed with the earlier postconditions.
+fn grouped<'a>(blocks: &'a [Block], options: &Options) -> Vec<&'a Block> {
+    let mut out = vec![];
+    let mut titles = vec![];
+    for block in blocks {
+        match section_title(block, options) {
+            None => out.push(block),
+            Some(title) if titles.contains(&title) => {}
+            Some(title) => {
+                titles.push(title);
+                out.extend(
+                    blocks
+                        .iter()
+                        .filter(|block| section_title(block, options) == Some(title)),
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Returns the label of a note, if it has one.
+fn note_label(kind: NoteKind, options: &Options) -> Option<&str> {
+    match kind {
+        NoteKind::Note => Some(&options.note_label),
+        NoteKind::Since => Some(&options.since_label),
+        NoteKind::Deprecated => Some(&options.deprecated_label),
+        NoteKind::Warning => Some(&options.warning_label),
+        NoteKind::Attention => Some(&options.attention_label),
+        NoteKind::Important => Some(&options.important_label),
+        NoteKind::Remark => None,
+    }
+}
+
+/// Prefixes all lines but the first, leaving blank lines without trailing whitespace.
+fn indent(text: &str, prefix: &str) -> String {
+    let lines = text.split('\n').enumerate().map(|(i, line)| match i {
+        0 => line.to_owned(),
+        _ if line.is_empty() => prefix.trim_end().to_owned(),
+        _ => format!("{prefix}{line}"),
+    });
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// Returns the anchors of all sections, along with their titles as plain text.
+fn anchors(doc: &DocComment) -> Vec<(&str, String)> {
+    let anchors = doc.blocks.iter().filter_map(|block| match block {
+        Block::Section { anchor, title, .. } if !anchor.is_empty() => {
+            Some((anchor.as_str(), inlines_text(title)))
+        }
+        _ => None,
+    });
+    anchors.collect()
+}
+
+/// Joins inline content into plain text.
+fn inlines_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => text.as_str(),
+            Inline::Code(word) | Inline::Emphasis(word) | Inline::Strong(word) => word,
+            Inline::Ref(reference) => reference.text.as_deref().unwrap_or(&reference.target),
+            Inline::Html(_) | Inline::Anchor(_) => "",
+            Inline::SoftBreak | Inline::LineBreak => " ",
+        })
+        .collect()
+}
+
+/// Escapes the HTML tags left in text when converting HTML, which are unbalanced or unknown.
+fn text_tags<'a>(text: &'a str, options: &Options) -> Cow<'a, str> {
+    if !options.convert_html || !text.contains('<') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        let tag = || text[i + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/');
+        match c {
+            '<' if tag() => out.push_str("&lt;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Returns the output level of a section heading, where level 1 is that of `@section`.
+fn section_level(level: u8, options: &Options) -> u8 {
+    options
+        .section_level
+        .saturating_add(level)
+        .saturating_sub(1)
+        .clamp(1, 6)
+}
+
+/// Returns the output level of a heading, where level 1 is that of the section headings.
+fn heading_level(level: u8, options: &Options) -> u8 {
+    options
+        .heading_level
+        .saturating_add(level)
+        .saturating_sub(1)
+        .clamp(1, 6)
+}
+
+/// Returns the info string of a code block, replacing languages rustdoc treats as Rust so that
+/// C samples are not compiled as doctests.
+fn code_language<'a>(language: Option<&'a str>, options: &'a Options) -> &'a str {
+    const RUSTDOC_ATTRIBUTES: [&str; 7] = [
+        "rust",
+        "ignore",
+        "should_panic",
+        "no_run",
+        "compile_fail",
+        "test_harness",
+        "standalone_crate",
+    ];
+    let rust = language.is_none_or(|language| {
+        language
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|attr| !attr.is_empty())
+            .all(|attr| {
+                RUSTDOC_ATTRIBUTES.contains(&attr)
+                    || attr.starts_with("edition")
+                    || attr.starts_with("ignore-")
+            })
+    });
+    match language {
+        Some(language) if !rust => language,
+        _ => &options.code_language,
+    }
+}
@@ -0,0 +1,126 @@
+//! Rendering of a [`DocComment`] as plain text.
+
+use super::{Renderer, grouped, indent, inlines_text, note_label, section_title};
+use crate::ast::{Block, DocComment, Param};
+use crate::transformer::Options;
+
+/// Renders plain text without any markup, e.g. for tooltips.
+///
+/// Soft line breaks are joined into a single line per paragraph.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlainText;
+
+impl Renderer for PlainText {
+    fn render(&self, doc: &DocComment, options: &Options) -> String {
+        let mut text = Writer {
+            options,
+            sections: vec![],
+        };
+        text.blocks(&doc.blocks)
+    }
+}
+
+struct Writer<'a> {
+    options: &'a Options,
+    /// Section titles emitted so far.
+    sections: Vec<&'a str>,
+}
+
+impl Writer<'_> {
+    fn blocks(&mut self, blocks: &[Block]) -> String {
+        let mut out = String::new();
+        // raw HTML has no plain text representation
+        let blocks = grouped(blocks, self.options)
+            .into_iter()
+            .filter(|block| !matches!(block, Block::Html(_)));
+        for (i, block) in blocks.enumerate() {
+            let title =
+                section_title(block, self.options).filter(|title| !self.sections.contains(title));
+            if i > 0 {
+                let tight = title.is_none()
+                    && matches!(block, Block::List { .. } | Block::Conditions { .. });
+                out.push_str(if tight { "\n" } else { "\n\n" });
+            }
+            if let Some(title) = title {
+                self.sections.push(title);
+                out.push_str(&format!("{title}:\n"));
+            }
+            out.push_str(&self.block(block));
+        }
+        out
+    }
+
+    fn block(&mut self, block: &Block) -> String {
+        match block {
+            Block::Paragraph(inlines) => inlines_text(inlines),
+            Block::Section { title, .. } => inlines_text(title),
+            Block::TitledParagraph { title, content } if content.is_empty() => inlines_text(title),
+            Block::TitledParagraph { title, content } => {
+                format!("{}\n{}", inlines_text(title), self.blocks(content))
+            }
+            Block::List { items, .. } | Block::Conditions { items, .. } => {
+                let ordered = matches!(block, Block::List { ordered: true, .. });
+                let items = items.iter().enumerate().map(|(i, item)| {
+                    let marker = if ordered {
+                        format!("{}.", i + 1)
+                    } else {
+                        self.options.bullet.to_string()
+                    };
+                    let prefix = " ".repeat(marker.len() + 1);
+                    format!("{marker} {}", indent(&self.blocks(item), &prefix))
+                });
+                items.collect::<Vec<_>>().join("\n")
+            }
+            Block::Code { code, .. } => code.clone(),
+            Block::Html(_) => String::new(),
+            Block::Note { kind, content } => match note_label(*kind, self.options) {
+                Some(label) => format!("{label}: {}", self.blocks(content)),
+                None => self.blocks(content),
+            },
+            Block::Params(params) | Block::TypeParams(params) => {
+                let params = params
+                    .iter()
+                    .map(|param| format!("  {}", indent(&self.param(param), "    ")));
+                params.collect::<Vec<_>>().join("\n")
+            }
+            Block::Returns(content) => format!("  {}", indent(&self.blocks(content), "  ")),
+            Block::ReturnValues(values) => {
+                let values = values.iter().map(|value| {
+                    let description = self.blocks(&value.description);
+                    format!("  {} - {}", value.value, indent(&description, "    "))
+                });
+                values.collect::<Vec<_>>().join("\n")
+            }
+            Block::Exceptions(exceptions) => {
+                let exceptions = exceptions.iter().map(|exception| {
+                    let description = self.blocks(&exception.description);
+                    format!("  {} - {}", exception.name, indent(&description, "    "))
+                });
+                exceptions.collect::<Vec<_>>().join("\n")
+            }
+            Block::SeeAlso(entries) => {
+                let entries = entries.iter().map(|entry| {
+                    let description = self.blocks(&entry.description);
+                    let text = match &entry.reference {
+                        Some(reference) if description.is_empty() => reference.target.clone(),
+                        Some(reference) => format!("{} {description}", reference.target),
+                        None => description,
+                    };
+                    format!("  {}", indent(&text, "    "))
+                });
+                entries.collect::<Vec<_>>().join("\n")
+            }
+        }
+    }
+
+    fn param(&mut self, param: &Param) -> String {
+        let mut out = param.name.clone();
+        if !param.attributes.is_empty() {
+            out.push_str(&format!(" [{}]", param.attributes.join(", ")));
+        }
+        if !param.description.is_empty() {
+            out.push_str(&format!(" - {}", self.blocks(&param.description)));
+        }
+        out
+    }
+}
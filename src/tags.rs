@@ -0,0 +1,130 @@
+//! Recognition of the HTML tags in comments.
+
+/// Elements that are kept as HTML when balanced, since markdown has no equivalent.
+const KEPT: &[&str] = &[
+    "div",
+    "span",
+    "table",
+    "caption",
+    "thead",
+    "tbody",
+    "tr",
+    "th",
+    "td",
+    "dl",
+    "dt",
+    "dd",
+    "blockquote",
+    "center",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "sup",
+    "sub",
+    "u",
+    "s",
+    "strike",
+    "del",
+    "ins",
+    "small",
+    "big",
+    "cite",
+    "dfn",
+    "kbd",
+    "var",
+    "hr",
+    "img",
+];
+
+/// Elements without content, which are balanced on their own.
+const VOID: &[&str] = &["br", "hr", "img"];
+
+/// An HTML start or end tag.
+pub(crate) struct Tag<'a> {
+    /// The lowercase element name.
+    pub(crate) name: String,
+    /// Whether this is an end tag such as `</b>`.
+    pub(crate) end: bool,
+    /// The attributes of a start tag.
+    attributes: &'a str,
+    /// Length of the tag in bytes.
+    pub(crate) len: usize,
+}
+
+impl<'a> Tag<'a> {
+    /// Returns the value of an attribute, e.g. the target of `<a href="...">`.
+    pub(crate) fn attribute(&self, name: &str) -> Option<&'a str> {
+        let mut rest = self.attributes;
+        loop {
+            rest = rest.trim_start();
+            let end = rest.find(|c: char| c == '=' || c.is_whitespace())?;
+            let (key, after) = rest.split_at(end);
+            let after = after.trim_start().strip_prefix('=')?.trim_start();
+            let (value, after) = match after.chars().next()? {
+                quote @ ('"' | '\'') => after[1..].split_once(quote)?,
+                _ => after.split_at(after.find(char::is_whitespace).unwrap_or(after.len())),
+            };
+            if key.eq_ignore_ascii_case(name) {
+                return Some(value);
+            }
+            rest = after;
+        }
+    }
+
+    /// Returns whether the element is kept as HTML rather than converted.
+    pub(crate) fn is_kept(&self) -> bool {
+        KEPT.contains(&self.name.as_str())
+    }
+
+    /// Returns whether the tag needs no end tag, e.g. `<br>` or `<img/>`.
+    pub(crate) fn is_void(&self) -> bool {
+        VOID.contains(&self.name.as_str()) || self.attributes.ends_with('/')
+    }
+}
+
+/// Returns the HTML tag at the start of `str`, if any.
+pub(crate) fn tag(str: &str) -> Option<Tag<'_>> {
+    let rest = str.strip_prefix('<')?;
+    let (end, rest) = match rest.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    let close = rest.find(['>', '<', '\n'])?;
+    if !rest[close..].starts_with('>') || close < name_len {
+        return None;
+    }
+    let attributes = &rest[name_len..close];
+    if !attributes.is_empty() && !attributes.starts_with([' ', '\t', '/']) {
+        return None;
+    }
+    Some(Tag {
+        name: rest[..name_len].to_ascii_lowercase(),
+        end,
+        attributes: attributes.trim(),
+        len: str.len() - rest.len() + close + 1,
+    })
+}
+
+/// Returns the offset of the end tag of `name` in `str`, ignoring case.
+pub(crate) fn find_end(str: &str, name: &str) -> Option<usize> {
+    let pattern = format!("</{name}>");
+    str.to_ascii_lowercase().find(&pattern)
+}
+
+/// Replaces the character references HTML-escaped code contains.
+pub(crate) fn decode_entities(str: &str) -> String {
+    str.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
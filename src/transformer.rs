@@ -0,0 +1,365 @@
+//! Configurable parsing and rendering of comments.
+
+use crate::ast::DocComment;
+use crate::error::{Diagnostic, TransformError};
+use crate::parse::{parse_lenient_with, parse_with};
+use crate::render::{Renderer, Rustdoc};
+
+/// Settings shared by the parser and all renderers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Options {
+    /// Level of section headings such as "Arguments", between 1 and 6.
+    pub heading_level: u8,
+    /// Level of `@section` headings, between 1 and 6. Subsections are nested below it.
+    pub section_level: u8,
+    pub arguments_title: String,
+    pub type_parameters_title: String,
+    pub returns_title: String,
+    pub exceptions_title: String,
+    /// Title of the `@pre` section, e.g. "Safety" for `unsafe` functions.
+    pub precondition_title: String,
+    pub postcondition_title: String,
+    pub invariant_title: String,
+    pub see_also_title: String,
+    pub note_label: String,
+    pub since_label: String,
+    pub deprecated_label: String,
+    pub warning_label: String,
+    pub attention_label: String,
+    pub important_label: String,
+    /// How the titles of `@par` paragraphs are rendered.
+    pub par_title_style: TitleStyle,
+    /// Labels of the conditional sections whose text is kept, like Doxygen's `ENABLED_SECTIONS`.
+    pub enabled_sections: Vec<String>,
+    /// Whether the text following `@internal` is kept rather than stripped.
+    pub keep_internal: bool,
+    /// Whether common HTML tags such as `<b>` and `<ul>` are converted into markdown, escaping
+    /// tags that are neither converted nor balanced.
+    pub convert_html: bool,
+    /// Bullet of `@li` and markdown list items.
+    pub bullet: char,
+    /// Bullet of `@param` items.
+    pub param_bullet: char,
+    /// How `@retval` entries are laid out.
+    pub return_value_style: ReturnValueStyle,
+    /// Whether `@retval` values become links to the constants of the same name.
+    pub link_return_values: bool,
+    /// Whether `@throws` types become intra-doc links.
+    pub link_exceptions: bool,
+    /// Info string of code blocks without a language, or with one that rustdoc would compile
+    /// as a doctest.
+    pub code_language: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            heading_level: 1,
+            section_level: 2,
+            arguments_title: "Arguments".to_owned(),
+            type_parameters_title: "Type Parameters".to_owned(),
+            returns_title: "Returns".to_owned(),
+            exceptions_title: "Exceptions".to_owned(),
+            precondition_title: "Safety".to_owned(),
+            postcondition_title: "Contracts".to_owned(),
+            invariant_title: "Contracts".to_owned(),
+            see_also_title: "See also".to_owned(),
+            note_label: "Note".to_owned(),
+            since_label: "Since".to_owned(),
+            deprecated_label: "Deprecated".to_owned(),
+            warning_label: "Warning".to_owned(),
+            attention_label: "Attention".to_owned(),
+            important_label: "Important".to_owned(),
+            par_title_style: TitleStyle::Heading,
+            enabled_sections: vec![],
+            keep_internal: false,
+            convert_html: false,
+            bullet: '-',
+            param_bullet: '*',
+            return_value_style: ReturnValueStyle::Table,
+            link_return_values: false,
+            link_exceptions: false,
+            code_language: "text".to_owned(),
+        }
+    }
+}
+
+/// Rendering of `@par` titles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TitleStyle {
+    /// A heading at the level of section headings.
+    #[default]
+    Heading,
+    /// A bold line above the paragraph.
+    Bold,
+}
+
+/// Layout of `@retval` entries in the Returns section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReturnValueStyle {
+    /// A two-column table of values and their meaning.
+    #[default]
+    Table,
+    /// A list of values followed by their meaning, like `@param` entries.
+    List,
+}
+
+/// Parses and renders comments with a fixed set of [`Options`].
+///
+/// ```
+/// let transformer = doxygen_bindgen::Transformer::builder()
+///     .heading_level(2)
+///     .arguments_title("Parameters")
+///     .build();
+/// let res = transformer.transform("@param x The value.").unwrap();
+/// assert_eq!(res, "## Parameters\n\n* `x` - The value.");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Transformer<R = Rustdoc> {
+    options: Options,
+    renderer: R,
+}
+
+impl Transformer {
+    /// Returns a builder with the default options and the [`Rustdoc`] renderer.
+    pub fn builder() -> TransformerBuilder {
+        TransformerBuilder::default()
+    }
+}
+
+impl<R: Renderer> Transformer<R> {
+    /// Returns the options in use.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Parses a Doxygen comment into a [`DocComment`].
+    pub fn parse(&self, str: &str) -> Result<DocComment, TransformError> {
+        parse_with(str, &self.options)
+    }
+
+    /// Renders a parsed comment.
+    pub fn render(&self, doc: &DocComment) -> String {
+        self.renderer.render(doc, &self.options)
+    }
+
+    /// Parses a Doxygen comment, keeping malformed commands as text instead of failing.
+    pub fn parse_lenient(&self, str: &str) -> (DocComment, Vec<Diagnostic>) {
+        parse_lenient_with(str, &self.options)
+    }
+
+    /// Transforms a Doxygen comment into the output format of the renderer.
+    pub fn transform(&self, str: &str) -> Result<String, TransformError> {
+        let doc = self.parse(str)?;
+        Ok(self.finish(&doc, str))
+    }
+
+    /// Transforms a Doxygen comment, keeping malformed commands as text instead of failing.
+    pub fn transform_lenient(&self, str: &str) -> (String, Vec<Diagnostic>) {
+        let (doc, diagnostics) = self.parse_lenient(str);
+        (self.finish(&doc, str), diagnostics)
+    }
+
+    /// Renders the transformed comment.
+    fn finish(&self, doc: &DocComment, str: &str) -> String {
+        let mut res = self.render(doc);
+        // keep the trailing line break of the comment
+        if !res.is_empty() && str.trim_end_matches([' ', '\t']).ends_with('\n') {
+            res.push('\n');
+        }
+        res
+    }
+}
+
+/// Builder for a [`Transformer`].
+#[derive(Clone, Debug, Default)]
+pub struct TransformerBuilder<R = Rustdoc> {
+    options: Options,
+    renderer: R,
+}
+
+impl<R> TransformerBuilder<R> {
+    /// Replaces all options.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the level of section headings, clamped to 1 through 6.
+    pub fn heading_level(mut self, level: u8) -> Self {
+        self.options.heading_level = level.clamp(1, 6);
+        self
+    }
+
+    /// Sets the level of `@section` headings, clamped to 1 through 6.
+    pub fn section_level(mut self, level: u8) -> Self {
+        self.options.section_level = level.clamp(1, 6);
+        self
+    }
+
+    /// Sets the title of the `@param` section.
+    pub fn arguments_title(mut self, title: impl Into<String>) -> Self {
+        self.options.arguments_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@tparam` section.
+    pub fn type_parameters_title(mut self, title: impl Into<String>) -> Self {
+        self.options.type_parameters_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@return` section.
+    pub fn returns_title(mut self, title: impl Into<String>) -> Self {
+        self.options.returns_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@throws` section.
+    pub fn exceptions_title(mut self, title: impl Into<String>) -> Self {
+        self.options.exceptions_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@pre` section.
+    pub fn precondition_title(mut self, title: impl Into<String>) -> Self {
+        self.options.precondition_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@post` section, which may equal that of another section to merge
+    /// them.
+    pub fn postcondition_title(mut self, title: impl Into<String>) -> Self {
+        self.options.postcondition_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@invariant` section.
+    pub fn invariant_title(mut self, title: impl Into<String>) -> Self {
+        self.options.invariant_title = title.into();
+        self
+    }
+
+    /// Sets the title of the `@see` section.
+    pub fn see_also_title(mut self, title: impl Into<String>) -> Self {
+        self.options.see_also_title = title.into();
+        self
+    }
+
+    /// Sets the label of `@note` callouts.
+    pub fn note_label(mut self, label: impl Into<String>) -> Self {
+        self.options.note_label = label.into();
+        self
+    }
+
+    /// Sets the label of `@since` callouts.
+    pub fn since_label(mut self, label: impl Into<String>) -> Self {
+        self.options.since_label = label.into();
+        self
+    }
+
+    /// Sets the label of `@deprecated` callouts.
+    pub fn deprecated_label(mut self, label: impl Into<String>) -> Self {
+        self.options.deprecated_label = label.into();
+        self
+    }
+
+    /// Sets the label of `@warning` callouts.
+    pub fn warning_label(mut self, label: impl Into<String>) -> Self {
+        self.options.warning_label = label.into();
+        self
+    }
+
+    /// Sets the label of `@attention` callouts.
+    pub fn attention_label(mut self, label: impl Into<String>) -> Self {
+        self.options.attention_label = label.into();
+        self
+    }
+
+    /// Sets the label of `@important` callouts.
+    pub fn important_label(mut self, label: impl Into<String>) -> Self {
+        self.options.important_label = label.into();
+        self
+    }
+
+    /// Sets how the titles of `@par` paragraphs are rendered.
+    pub fn par_title_style(mut self, style: TitleStyle) -> Self {
+        self.options.par_title_style = style;
+        self
+    }
+
+    /// Enables a conditional section label, keeping the text of `@if` and `@cond` sections that
+    /// depend on it.
+    pub fn enable_section(mut self, label: impl Into<String>) -> Self {
+        self.options.enabled_sections.push(label.into());
+        self
+    }
+
+    /// Keeps the text following `@internal`, e.g. for documentation of private builds.
+    pub fn keep_internal(mut self, keep: bool) -> Self {
+        self.options.keep_internal = keep;
+        self
+    }
+
+    /// Converts HTML tags into markdown, e.g. `<b>` into `**` and `<li>` into list items, so
+    /// rustdoc does not warn about unbalanced tags.
+    pub fn convert_html(mut self, convert: bool) -> Self {
+        self.options.convert_html = convert;
+        self
+    }
+
+    /// Sets the bullet of list items, e.g. `-` or `*`.
+    pub fn bullet(mut self, bullet: char) -> Self {
+        self.options.bullet = bullet;
+        self
+    }
+
+    /// Sets the bullet of `@param` items.
+    pub fn param_bullet(mut self, bullet: char) -> Self {
+        self.options.param_bullet = bullet;
+        self
+    }
+
+    /// Sets the layout of `@retval` entries.
+    pub fn return_value_style(mut self, style: ReturnValueStyle) -> Self {
+        self.options.return_value_style = style;
+        self
+    }
+
+    /// Links `@retval` values to the constants of the same name, e.g. bindgen's `STATUS_*`
+    /// constants.
+    pub fn link_return_values(mut self, link: bool) -> Self {
+        self.options.link_return_values = link;
+        self
+    }
+
+    /// Links `@throws` types to the items of the same name.
+    pub fn link_exceptions(mut self, link: bool) -> Self {
+        self.options.link_exceptions = link;
+        self
+    }
+
+    /// Sets the info string of code blocks that would otherwise be compiled as doctests, e.g.
+    /// `c` or `ignore`.
+    pub fn code_language(mut self, language: impl Into<String>) -> Self {
+        self.options.code_language = language.into();
+        self
+    }
+
+    /// Sets the output format.
+    pub fn renderer<T: Renderer>(self, renderer: T) -> TransformerBuilder<T> {
+        TransformerBuilder {
+            options: self.options,
+            renderer,
+        }
+    }
+
+    /// Builds the transformer.
+    pub fn build(self) -> Transformer<R> {
+        Transformer {
+            options: self.options,
+            renderer: self.renderer,
+        }
+    }
+}